@@ -0,0 +1,262 @@
+use std::io;
+use std::rc::Rc;
+use std::time::Duration;
+use futures::{future, Future};
+use rustc_serialize::Encodable;
+use futures::future::{Either, Loop};
+use tokio_core::reactor::Handle;
+use url::Url;
+
+use async_transport::HyperAsyncTransport;
+use client::{self, SolrClient, SolrClientBuilder};
+use document::SolrDocument;
+use query::SolrQuery;
+use request::{SolrDeleteRequest, SolrUpdateBatch, SolrUpdateOptions};
+use response::{SolrError, SolrPingResponse, SolrQueryResponse, SolrUpdateResponse};
+use xml::ToXml;
+use transport::{AsyncTransport, HttpBody, HttpMethod, HttpRequest, HttpResponse};
+
+/// Future resolved with the result of a SolrAsyncClient request
+pub type SolrFuture<T> = Box<dyn Future<Item=T, Error=SolrError>>;
+
+/// Asynchronous counterpart of SolrClient, returning futures instead of blocking.
+/// Requests are built and responses parsed like by SolrClient, and sent by an AsyncTransport,
+/// by default HyperAsyncTransport, without blocking the thread running the reactor.
+/// Clones share the transport.
+#[derive(Clone)]
+pub struct SolrAsyncClient {
+    client: SolrClient,
+    transport: Rc<dyn AsyncTransport>
+}
+
+impl SolrAsyncClient {
+    /// Creates a client sending requests with HyperAsyncTransport on the reactor of `handle`
+    pub fn new(url: &Url, handle: &Handle) -> SolrAsyncClient {
+        SolrAsyncClient::with_transport(SolrClient::new(url), HyperAsyncTransport::new(handle))
+    }
+
+    /// Creates a client configured by `builder`, sending requests with a HyperAsyncTransport
+    /// using its timeout, keep-alive and compression settings on the reactor of `handle`.
+    /// Fails with an `InvalidInput` error when a proxy is set, which the transport doesn't support.
+    pub fn from_builder(builder: &SolrClientBuilder, handle: &Handle) -> io::Result<SolrAsyncClient> {
+        let transport = try!(HyperAsyncTransport::with_config(handle, client::transport_config(builder)));
+        Ok(SolrAsyncClient::with_transport(builder.build(), transport))
+    }
+
+    /// Creates a client sending the requests `client` would send, with its URLs, update format,
+    /// credentials, default headers and retry policy, through an asynchronous transport.
+    /// Connection, TLS, proxy and compression settings of `client` aren't used by `transport`.
+    pub fn with_transport<T: AsyncTransport + 'static>(client: SolrClient, transport: T) -> SolrAsyncClient {
+        SolrAsyncClient { client, transport: Rc::new(transport) }
+    }
+
+    /// Performs Solr ping request
+    pub fn ping(&self) -> SolrFuture<SolrPingResponse> {
+        self.execute(true, &[], client::ping_request(&self.client), client::handle_http_ping_result)
+    }
+
+    /// Performs Solr query
+    pub fn query(&self, query: &SolrQuery) -> SolrFuture<SolrQueryResponse> {
+        let params = client::query_params(&query.to_pairs());
+        self.execute(true, query.get_headers(), client::query_request(&self.client, query, &params),
+                     client::handle_http_query_result)
+    }
+
+    /// Performs Solr query with the JSON Request API
+    pub fn query_json(&self, query: &SolrQuery) -> SolrFuture<SolrQueryResponse> {
+        let body = query.to_json().to_string();
+        self.execute(true, query.get_headers(), client::json_query_request(&self.client, query, &body),
+                     client::handle_http_query_result)
+    }
+
+    /// Fetches the latest version of a document by its unique key with the real-time get handler
+    pub fn get_by_id(&self, id: &str) -> SolrFuture<Option<SolrDocument>> {
+        Box::new(self.get_by_ids(&[id]).map(|response| response.items.into_iter().next()))
+    }
+
+    /// Fetches the latest versions of documents by their unique keys with the real-time get handler.
+    /// Documents which don't exist are left out.
+    pub fn get_by_ids(&self, ids: &[&str]) -> SolrFuture<SolrQueryResponse> {
+        self.execute(true, &[], client::get_request(&self.client, ids), client::handle_http_query_result)
+    }
+
+    /// Adds new document to Solr, without committing
+    pub fn add(&self, document: &SolrDocument) -> SolrFuture<SolrUpdateResponse> {
+        self.add_many_with_options(&[document], &SolrUpdateOptions::new())
+    }
+
+    /// Adds new document to Solr using given update options
+    pub fn add_with_options(&self, document: &SolrDocument, options: &SolrUpdateOptions) -> SolrFuture<SolrUpdateResponse> {
+        self.add_many_with_options(&[document], options)
+    }
+
+    /// Adds new document to Solr and commits it
+    pub fn add_and_commit(&self, document: &SolrDocument) -> SolrFuture<SolrUpdateResponse> {
+        self.add_many_with_options(&[document], &SolrUpdateOptions::new().commit(true))
+    }
+
+    /// Adds multiple documents to Solr, without committing
+    pub fn add_many(&self, documents: &[&SolrDocument]) -> SolrFuture<SolrUpdateResponse> {
+        self.add_many_with_options(documents, &SolrUpdateOptions::new())
+    }
+
+    /// Adds multiple documents to Solr and commits them
+    pub fn add_many_and_commit(&self, documents: &[&SolrDocument]) -> SolrFuture<SolrUpdateResponse> {
+        self.add_many_with_options(documents, &SolrUpdateOptions::new().commit(true))
+    }
+
+    /// Adds multiple documents to Solr using given update options
+    pub fn add_many_with_options(&self, documents: &[&SolrDocument], options: &SolrUpdateOptions) -> SolrFuture<SolrUpdateResponse> {
+        match client::add_body(&self.client, documents) {
+            Ok(body) => self.post_update_body(&body, options),
+            Err(err) => Box::new(future::err(err))
+        }
+    }
+
+    /// Performs an explicit commit, causing pending documents to be indexed
+    pub fn commit(&self) -> SolrFuture<SolrUpdateResponse> {
+        self.commit_with_options(&SolrUpdateOptions::new())
+    }
+
+    /// Performs an explicit commit using given update options,
+    /// e.g. soft commit or commit with expunging deletes
+    pub fn commit_with_options(&self, options: &SolrUpdateOptions) -> SolrFuture<SolrUpdateResponse> {
+        self.post_update_body("", &options.commit(true))
+    }
+
+    /// Performs a rollback of all non-committed documents
+    pub fn rollback(&self) -> SolrFuture<SolrUpdateResponse> {
+        self.rollback_with_options(&SolrUpdateOptions::new())
+    }
+
+    /// Performs a rollback using given update options, e.g. with headers or a timeout
+    pub fn rollback_with_options(&self, options: &SolrUpdateOptions) -> SolrFuture<SolrUpdateResponse> {
+        self.execute(false, options.get_headers(), client::rollback_request(&self.client, options),
+                     client::handle_http_update_result)
+    }
+
+    /// Performs an explicit optimize, causing a merge of all segments to one.
+    pub fn optimize(&self) -> SolrFuture<SolrUpdateResponse> {
+        self.optimize_with_options(&SolrUpdateOptions::new())
+    }
+
+    /// Performs an explicit optimize using given update options,
+    /// e.g. merging down to `max_segments` instead of one
+    pub fn optimize_with_options(&self, options: &SolrUpdateOptions) -> SolrFuture<SolrUpdateResponse> {
+        self.post_update_body("", &options.optimize(true))
+    }
+
+    /// Deletes a single document by a unique ID
+    pub fn delete_by_id(&self, id: &str) -> SolrFuture<SolrUpdateResponse> {
+        self.delete_by_id_with_options(id, &SolrUpdateOptions::new().commit(true))
+    }
+
+    /// Deletes a single document by a unique ID using given update options
+    pub fn delete_by_id_with_options(&self, id: &str, options: &SolrUpdateOptions) -> SolrFuture<SolrUpdateResponse> {
+        self.post_update(&SolrDeleteRequest::from_id(id), options)
+    }
+
+    /// Deletes a list of documents by IDs
+    pub fn delete_by_ids(&self, ids: &Vec<String>) -> SolrFuture<SolrUpdateResponse> {
+        self.delete_by_ids_with_options(ids, &SolrUpdateOptions::new().commit(true))
+    }
+
+    /// Deletes a list of documents by IDs using given update options
+    pub fn delete_by_ids_with_options(&self, ids: &Vec<String>, options: &SolrUpdateOptions) -> SolrFuture<SolrUpdateResponse> {
+        self.post_update(&SolrDeleteRequest::from_ids(ids), options)
+    }
+
+    /// Deletes documents from the index by query
+    pub fn delete_by_query(&self, query: &str) -> SolrFuture<SolrUpdateResponse> {
+        self.delete_by_query_with_options(query, &SolrUpdateOptions::new().commit(true))
+    }
+
+    /// Deletes documents from the index by query using given update options
+    pub fn delete_by_query_with_options(&self, query: &str, options: &SolrUpdateOptions) -> SolrFuture<SolrUpdateResponse> {
+        self.post_update(&SolrDeleteRequest::from_query(query), options)
+    }
+
+    /// Sends a batch of add, delete, commit and optimize commands in a single request
+    pub fn update_batch(&self, batch: &SolrUpdateBatch) -> SolrFuture<SolrUpdateResponse> {
+        self.update_batch_with_options(batch, &SolrUpdateOptions::new())
+    }
+
+    /// Sends a batch of update commands in a single request using given update options
+    pub fn update_batch_with_options(&self, batch: &SolrUpdateBatch, options: &SolrUpdateOptions) -> SolrFuture<SolrUpdateResponse> {
+        self.post_update(batch, options)
+    }
+
+    fn post_update<T: Encodable + ToXml>(&self, data: &T, options: &SolrUpdateOptions) -> SolrFuture<SolrUpdateResponse> {
+        match client::update_body(&self.client, data) {
+            Ok(body) => self.post_update_body(&body, options),
+            Err(err) => Box::new(future::err(err))
+        }
+    }
+
+    fn post_update_body(&self, body: &str, options: &SolrUpdateOptions) -> SolrFuture<SolrUpdateResponse> {
+        self.execute(false, options.get_headers(), client::update_request(&self.client, body, options),
+                     client::handle_http_update_result)
+    }
+
+    // Sends a request, retrying it according to the retry policy like SolrClient does,
+    // and handles the last response
+    fn execute<T, F>(&self, idempotent: bool, headers: &[(String, String)], request: HttpRequest, handle: F) -> SolrFuture<T>
+        where T: 'static, F: FnOnce(io::Result<HttpResponse>) -> Result<T, SolrError> + 'static {
+        let request = match BufferedRequest::new(client::with_headers(&self.client, request, headers)) {
+            Ok(request) => Rc::new(request),
+            Err(err) => return Box::new(future::result(handle(Err(err))))
+        };
+        let policy = client::retry_policy(&self.client, idempotent).cloned();
+        let transport = self.transport.clone();
+        let sent = future::loop_fn(1, move |attempt| {
+            let (transport, policy) = (transport.clone(), policy.clone());
+            transport.send(request.to_request()).then(move |result| match policy {
+                Some(ref policy) if attempt < policy.get_max_attempts() && policy.should_retry(&result) => {
+                    Either::A(transport.delay(policy.backoff(attempt)).then(move |delayed| match delayed {
+                        Ok(()) => Ok(Loop::Continue(attempt + 1)),
+                        Err(err) => Ok(Loop::Break((Err(err), attempt)))
+                    }))
+                },
+                _ => Either::B(future::ok::<_, SolrError>(Loop::Break((result, attempt))))
+            })
+        });
+        Box::new(sent.and_then(move |(result, attempts)| client::with_attempts(handle(result), attempts)))
+    }
+}
+
+// Request owning its body, so it can be sent again when retried
+struct BufferedRequest {
+    method: HttpMethod,
+    url: Url,
+    headers: Vec<(String, String)>,
+    body: Option<String>,
+    timeout: Option<Duration>
+}
+
+impl BufferedRequest {
+    fn new(request: HttpRequest) -> io::Result<BufferedRequest> {
+        let body = match request.body {
+            HttpBody::Empty => None,
+            HttpBody::Text(text) => Some(text.to_string()),
+            HttpBody::Stream(reader) => {
+                let mut body = String::new();
+                try!(reader.read_to_string(&mut body));
+                Some(body)
+            }
+        };
+        Ok(BufferedRequest { method: request.method, url: request.url, headers: request.headers, body, timeout: request.timeout })
+    }
+
+    fn to_request(&self) -> HttpRequest<'_> {
+        HttpRequest {
+            method: self.method,
+            url: self.url.clone(),
+            headers: self.headers.clone(),
+            body: match self.body {
+                Some(ref body) => HttpBody::Text(body),
+                None => HttpBody::Empty
+            },
+            timeout: self.timeout
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use std::io::{self, Cursor};
+use std::time::Duration;
+use futures::{future, Future, Stream};
+use hyper_async::{self, Client, Method, Request, Uri};
+use hyper_async::client::HttpConnector;
+use tokio_core::reactor::{Handle, Timeout};
+
+use http_utils::{self, HyperTransportConfig};
+use transport::{AsyncTransport, HttpBody, HttpFuture, HttpMethod, HttpRequest, HttpResponse};
+
+/// Default asynchronous transport, sending requests with hyper over non-blocking
+/// keep-alive connections driven by a tokio reactor.
+/// Requests to https URLs fail with an `InvalidInput` error, as TLS isn't supported;
+/// send them with another AsyncTransport.
+#[derive(Clone)]
+pub struct HyperAsyncTransport {
+    client: Client<HttpConnector>,
+    handle: Handle,
+    timeout: Option<Duration>,
+    response_compression: bool,
+    request_compression: Option<usize>
+}
+
+impl HyperAsyncTransport {
+    /// Creates a transport running on the reactor of `handle`, accepting compressed responses
+    pub fn new(handle: &Handle) -> HyperAsyncTransport {
+        HyperAsyncTransport {
+            client: Client::new(handle),
+            handle: handle.clone(),
+            timeout: None,
+            response_compression: true,
+            request_compression: None
+        }
+    }
+
+    /// Creates a transport with the settings of HyperTransport. The read timeout bounds
+    /// the whole request. Fails with an `InvalidInput` error when a proxy is set,
+    /// since requests can't be sent through it.
+    pub fn with_config(handle: &Handle, config: &HyperTransportConfig) -> io::Result<HyperAsyncTransport> {
+        if config.proxy.is_some() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "HyperAsyncTransport doesn't support proxies"));
+        }
+        let client = Client::configure().keep_alive_timeout(config.idle_timeout).build(handle);
+        Ok(HyperAsyncTransport {
+            client,
+            handle: handle.clone(),
+            timeout: config.read_timeout,
+            response_compression: config.response_compression,
+            request_compression: config.request_compression
+        })
+    }
+
+    /// Sets the maximum time to wait for a response, unless overridden by the request
+    pub fn timeout(&self, timeout: Duration) -> HyperAsyncTransport {
+        let mut transport = self.clone();
+        transport.timeout = Some(timeout);
+        transport
+    }
+
+    /// Sets whether gzip and deflate compressed responses are accepted, true by default
+    pub fn response_compression(&self, enabled: bool) -> HyperAsyncTransport {
+        let mut transport = self.clone();
+        transport.response_compression = enabled;
+        transport
+    }
+
+    /// Compresses request bodies of at least `min_size` bytes with gzip
+    pub fn request_compression(&self, min_size: usize) -> HyperAsyncTransport {
+        let mut transport = self.clone();
+        transport.request_compression = Some(min_size);
+        transport
+    }
+}
+
+impl AsyncTransport for HyperAsyncTransport {
+    fn send(&self, request: HttpRequest) -> HttpFuture {
+        if request.url.scheme != "http" {
+            let message = format!("HyperAsyncTransport supports only http URLs, not {}", request.url.serialize());
+            return Box::new(future::err(io::Error::new(io::ErrorKind::InvalidInput, message)));
+        }
+        let uri = match request.url.serialize().parse::<Uri>() {
+            Ok(uri) => uri,
+            Err(err) => return Box::new(future::err(io::Error::new(io::ErrorKind::InvalidInput, err)))
+        };
+        let method = match request.method {
+            HttpMethod::Get => Method::Get,
+            HttpMethod::Post => Method::Post
+        };
+        let mut http_request = Request::new(method, uri);
+        for (name, value) in request.headers {
+            http_request.headers_mut().set_raw(name, value);
+        }
+        if self.response_compression && http_request.headers().get_raw("Accept-Encoding").is_none() {
+            http_request.headers_mut().set_raw("Accept-Encoding", "gzip, deflate");
+        }
+        // Solr reads form parameters before any decompression, so forms are sent as they are
+        let is_form = http_request.headers().get_raw("Content-Type")
+            .is_some_and(|value| value.one() == Some(&b"application/x-www-form-urlencoded"[..]));
+        match request.body {
+            HttpBody::Empty => {},
+            HttpBody::Text(text) => match self.request_compression {
+                Some(min_size) if text.len() >= min_size && !is_form => match http_utils::gzip(text) {
+                    Ok(compressed) => {
+                        http_request.headers_mut().set_raw("Content-Encoding", "gzip");
+                        http_request.set_body(compressed);
+                    },
+                    Err(err) => return Box::new(future::err(err))
+                },
+                _ => http_request.set_body(text.to_string())
+            },
+            HttpBody::Stream(reader) => {
+                let mut body = Vec::new();
+                if let Err(err) = reader.read_to_end(&mut body) {
+                    return Box::new(future::err(err));
+                }
+                http_request.set_body(body);
+            }
+        }
+        let response = self.client.request(http_request).and_then(|response| {
+            let status = response.status().as_u16();
+            let encoding = response.headers().get_raw("Content-Encoding").and_then(|value| value.one())
+                .map_or(String::new(), |value| String::from_utf8_lossy(value).trim().to_lowercase());
+            let decoded = http_utils::is_decoded(&encoding);
+            // decoded bodies are returned without the headers describing the encoded ones
+            let headers = response.headers().iter()
+                .filter(|header| !decoded || !(header.name().eq_ignore_ascii_case("Content-Encoding") ||
+                                               header.name().eq_ignore_ascii_case("Content-Length")))
+                .map(|header| (header.name().to_string(), header.value_string()))
+                .collect();
+            response.body().concat2().map(move |body| {
+                HttpResponse { status, headers, body: http_utils::decode(&encoding, Cursor::new(body.to_vec())) }
+            })
+        }).map_err(to_io_error);
+        match request.timeout.or(self.timeout) {
+            Some(timeout) => with_timeout(response, timeout, &self.handle),
+            None => Box::new(response)
+        }
+    }
+
+    fn delay(&self, duration: Duration) -> Box<dyn Future<Item=(), Error=io::Error>> {
+        match Timeout::new(duration, &self.handle) {
+            Ok(timeout) => Box::new(timeout),
+            Err(err) => Box::new(future::err(err))
+        }
+    }
+}
+
+// Fails with a TimedOut error unless the response arrives in time
+fn with_timeout<F>(response: F, timeout: Duration, handle: &Handle) -> HttpFuture
+    where F: Future<Item=HttpResponse, Error=io::Error> + 'static {
+    let elapsed = match Timeout::new(timeout, handle) {
+        Ok(elapsed) => elapsed,
+        Err(err) => return Box::new(future::err(err))
+    };
+    let elapsed = elapsed.then(|_| Err(io::Error::new(io::ErrorKind::TimedOut, "No response within the timeout")));
+    Box::new(response.select(elapsed).map(|(response, _)| response).map_err(|(err, _)| err))
+}
+
+fn to_io_error(err: hyper_async::Error) -> io::Error {
+    match err {
+        hyper_async::Error::Io(err) => err,
+        err => io::Error::other(err)
+    }
+}
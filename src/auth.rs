@@ -0,0 +1,29 @@
+use std::fmt;
+use rustc_serialize::base64::{ToBase64, STANDARD};
+
+/// Username and password for HTTP Basic authentication,
+/// as required by Solr's BasicAuth security plugin.
+/// The password is never printed by `Debug`.
+#[derive(Clone, PartialEq)]
+pub struct SolrCredentials {
+    pub username: String,
+    password: String
+}
+
+impl SolrCredentials {
+    /// Creates credentials from a username and a password
+    pub fn new(username: &str, password: &str) -> SolrCredentials {
+        SolrCredentials { username: username.to_string(), password: password.to_string() }
+    }
+
+    /// Returns the value of the Authorization header
+    pub fn authorization(&self) -> String {
+        format!("Basic {}", format!("{}:{}", self.username, self.password).as_bytes().to_base64(STANDARD))
+    }
+}
+
+impl fmt::Debug for SolrCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SolrCredentials").field("username", &self.username).field("password", &"<redacted>").finish()
+    }
+}
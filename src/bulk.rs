@@ -0,0 +1,306 @@
+use std::cmp;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, RecvTimeoutError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use client::SolrClient;
+use document::{SolrDocument, SolrValue};
+use request::SolrUpdateOptions;
+use response::{SolrError, SolrUpdateResult};
+
+/// Outcome of a single batch sent by BulkIndexer
+pub struct BatchOutcome {
+    /// Sequence number of the batch, starting from 1
+    pub batch: u64,
+    /// Number of documents in the batch
+    pub documents: usize,
+    /// Number of attempts it took, including the first one
+    pub attempts: u32,
+    /// Result of the last attempt
+    pub result: SolrUpdateResult
+}
+
+/// Final totals reported by BulkIndexer when it's finished
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct BulkIndexerTotals {
+    /// Number of batches sent
+    pub batches: u64,
+    /// Number of batches that failed after all retries
+    pub failed_batches: u64,
+    /// Number of documents successfully indexed
+    pub indexed: u64,
+    /// Number of documents in failed batches
+    pub failed: u64,
+    /// Number of retries performed
+    pub retries: u64
+}
+
+type BatchCallback = Arc<dyn Fn(&BatchOutcome) + Send + Sync>;
+
+/// Configuration of BulkIndexer: batch size limits, flush interval, number of workers,
+/// retries of failed batches and update options of every batch.
+#[derive(Clone)]
+pub struct BulkIndexerOptions {
+    max_documents: usize,
+    max_bytes: usize,
+    flush_interval: Option<Duration>,
+    workers: usize,
+    max_retries: u32,
+    retry_delay: Duration,
+    update_options: SolrUpdateOptions,
+    on_batch: Option<BatchCallback>
+}
+
+impl BulkIndexerOptions {
+    /// Creates options with defaults: batches of 500 documents or 5MB,
+    /// flushed at least every second, one worker and 3 retries
+    pub fn new() -> BulkIndexerOptions {
+        BulkIndexerOptions {
+            max_documents: 500,
+            max_bytes: 5 * 1024 * 1024,
+            flush_interval: Some(Duration::from_secs(1)),
+            workers: 1,
+            max_retries: 3,
+            retry_delay: Duration::from_millis(500),
+            update_options: SolrUpdateOptions::new(),
+            on_batch: None
+        }
+    }
+
+    /// Flushes a batch once it has this many documents
+    pub fn max_documents(&self, max_documents: usize) -> BulkIndexerOptions {
+        let mut options = self.clone();
+        options.max_documents = cmp::max(max_documents, 1);
+        options
+    }
+
+    /// Flushes a batch once its estimated JSON size reaches this many bytes
+    pub fn max_bytes(&self, max_bytes: usize) -> BulkIndexerOptions {
+        let mut options = self.clone();
+        options.max_bytes = max_bytes;
+        options
+    }
+
+    /// Flushes a non-empty batch once its first document waited this long.
+    /// `None` disables time based flushing.
+    pub fn flush_interval(&self, flush_interval: Option<Duration>) -> BulkIndexerOptions {
+        let mut options = self.clone();
+        options.flush_interval = flush_interval;
+        options
+    }
+
+    /// Sets the number of threads sending batches to Solr concurrently
+    pub fn workers(&self, workers: usize) -> BulkIndexerOptions {
+        let mut options = self.clone();
+        options.workers = cmp::max(workers, 1);
+        options
+    }
+
+    /// Sets how many times a batch failing with a transport error, a timeout or a server error is retried.
+    /// Batches Solr rejects, e.g. with an invalid document, are reported right away.
+    pub fn max_retries(&self, max_retries: u32) -> BulkIndexerOptions {
+        let mut options = self.clone();
+        options.max_retries = max_retries;
+        options
+    }
+
+    /// Sets the delay before the first retry, doubled on every next one
+    pub fn retry_delay(&self, retry_delay: Duration) -> BulkIndexerOptions {
+        let mut options = self.clone();
+        options.retry_delay = retry_delay;
+        options
+    }
+
+    /// Sets update options every batch is sent with, e.g. `commit_within`
+    pub fn update_options(&self, update_options: &SolrUpdateOptions) -> BulkIndexerOptions {
+        let mut options = self.clone();
+        options.update_options = update_options.clone();
+        options
+    }
+
+    /// Sets a callback invoked from worker threads with the outcome of every batch
+    pub fn on_batch<F>(&self, callback: F) -> BulkIndexerOptions
+        where F: Fn(&BatchOutcome) + Send + Sync + 'static {
+        let mut options = self.clone();
+        options.on_batch = Some(Arc::new(callback));
+        options
+    }
+}
+
+impl Default for BulkIndexerOptions {
+    fn default() -> BulkIndexerOptions {
+        BulkIndexerOptions::new()
+    }
+}
+
+enum Message {
+    Add(SolrDocument),
+    Flush
+}
+
+struct Batch {
+    number: u64,
+    documents: Vec<SolrDocument>
+}
+
+/// Buffers documents and indexes them in batches from background threads.
+/// Call `finish` to flush remaining documents and wait for all batches to be sent.
+pub struct BulkIndexer {
+    sender: SyncSender<Message>,
+    collector: JoinHandle<()>,
+    workers: Vec<JoinHandle<()>>,
+    totals: Arc<Mutex<BulkIndexerTotals>>
+}
+
+impl BulkIndexer {
+    /// Creates a new BulkIndexer sending documents through a given client
+    pub fn new(client: SolrClient, options: BulkIndexerOptions) -> BulkIndexer {
+        let client = Arc::new(client);
+        let totals = Arc::new(Mutex::new(BulkIndexerTotals::default()));
+        let (sender, receiver) = sync_channel(options.max_documents);
+        let (batch_sender, batch_receiver) = sync_channel(options.workers);
+        let batch_receiver = Arc::new(Mutex::new(batch_receiver));
+
+        let workers = (0..options.workers).map(|_| {
+            let client = client.clone();
+            let options = options.clone();
+            let batch_receiver = batch_receiver.clone();
+            let totals = totals.clone();
+            thread::spawn(move || run_worker(&client, &options, &batch_receiver, &totals))
+        }).collect();
+        let collector_options = options.clone();
+        let collector = thread::spawn(move || run_collector(&collector_options, receiver, batch_sender));
+
+        BulkIndexer { sender, collector, workers, totals }
+    }
+
+    /// Queues a document for indexing.
+    /// Blocks when workers fall behind and the buffer is full.
+    pub fn add(&self, document: SolrDocument) {
+        self.sender.send(Message::Add(document)).expect("BulkIndexer collector thread has stopped");
+    }
+
+    /// Sends buffered documents as a batch without waiting for the batch to fill up
+    pub fn flush(&self) {
+        self.sender.send(Message::Flush).expect("BulkIndexer collector thread has stopped");
+    }
+
+    /// Sends remaining documents, waits for all batches and returns final totals
+    pub fn finish(self) -> BulkIndexerTotals {
+        drop(self.sender);
+        self.collector.join().expect("BulkIndexer collector thread panicked");
+        for worker in self.workers {
+            worker.join().expect("BulkIndexer worker thread panicked");
+        }
+        let totals = self.totals.lock().unwrap();
+        *totals
+    }
+}
+
+fn run_collector(options: &BulkIndexerOptions, receiver: Receiver<Message>, batch_sender: SyncSender<Batch>) {
+    let mut number = 0u64;
+    let mut documents = Vec::with_capacity(options.max_documents);
+    let mut bytes = 0usize;
+    let mut started = Instant::now();
+    loop {
+        let message = match options.flush_interval {
+            Some(interval) if !documents.is_empty() => {
+                let elapsed = started.elapsed();
+                if elapsed >= interval {
+                    Err(RecvTimeoutError::Timeout)
+                } else {
+                    receiver.recv_timeout(interval - elapsed)
+                }
+            },
+            _ => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected)
+        };
+        let flush = match message {
+            Ok(Message::Add(document)) => {
+                if documents.is_empty() {
+                    started = Instant::now();
+                }
+                // +1 for the separating comma in the JSON array
+                bytes += estimated_size(&document) + 1;
+                documents.push(document);
+                documents.len() >= options.max_documents || bytes >= options.max_bytes
+            },
+            Ok(Message::Flush) | Err(RecvTimeoutError::Timeout) => true,
+            Err(RecvTimeoutError::Disconnected) => break
+        };
+        if flush && !documents.is_empty() {
+            number += 1;
+            let batch = Batch { number, documents };
+            documents = Vec::with_capacity(options.max_documents);
+            bytes = 0;
+            if batch_sender.send(batch).is_err() {
+                return;
+            }
+        }
+    }
+    if !documents.is_empty() {
+        let _ = batch_sender.send(Batch { number: number + 1, documents });
+    }
+}
+
+fn run_worker(client: &SolrClient, options: &BulkIndexerOptions,
+              receiver: &Mutex<Receiver<Batch>>, totals: &Mutex<BulkIndexerTotals>) {
+    loop {
+        let batch = match receiver.lock().unwrap().recv() {
+            Ok(batch) => batch,
+            Err(_) => return
+        };
+        let documents: Vec<&SolrDocument> = batch.documents.iter().collect();
+        let mut attempts = 0u32;
+        let mut delay = options.retry_delay;
+        let result = loop {
+            attempts += 1;
+            let result = client.add_many_with_options(&documents, &options.update_options);
+            if !result.as_ref().err().is_some_and(is_retryable) || attempts > options.max_retries {
+                break result;
+            }
+            thread::sleep(delay);
+            delay *= 2;
+        };
+        {
+            let mut totals = totals.lock().unwrap();
+            totals.batches += 1;
+            totals.retries += (attempts - 1) as u64;
+            if result.is_ok() {
+                totals.indexed += documents.len() as u64;
+            } else {
+                totals.failed_batches += 1;
+                totals.failed += documents.len() as u64;
+            }
+        }
+        if let Some(ref callback) = options.on_batch {
+            callback(&BatchOutcome { batch: batch.number, documents: documents.len(), attempts, result });
+        }
+    }
+}
+
+// Errors which sending the same batch again may not run into
+fn is_retryable(err: &SolrError) -> bool {
+    match *err {
+        SolrError::Transport { .. } | SolrError::Timeout { .. } | SolrError::Unavailable(_) => true,
+        SolrError::Http { status, .. } => status >= 500,
+        _ => false
+    }
+}
+
+// Approximate JSON size of a document, computed without encoding it, which the worker does.
+// Escaping is ignored and numbers are counted at their longest.
+fn estimated_size(document: &SolrDocument) -> usize {
+    let fields: usize = document.iter().map(|field| {
+        let value = match field.value {
+            SolrValue::String(ref value) => value.len() + 2,
+            SolrValue::I64(_) | SolrValue::U64(_) | SolrValue::F64(_) => 20,
+            SolrValue::Boolean(_) => 5,
+            SolrValue::Null => 4
+        };
+        // Quoted name, colon and comma, and the object wrapping atomic updates or boosts
+        let wrapper = if field.update.is_some() || field.boost.is_some() { 20 } else { 0 };
+        field.name.len() + 4 + value + wrapper
+    }).sum();
+    fields + 2
+}
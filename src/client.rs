@@ -1,29 +1,55 @@
-use url::{Url, UrlParser};
-use rustc_serialize::{json};
-use hyper::error::Error;
+use url::{form_urlencoded, Url, UrlParser};
+use rustc_serialize::{json, Encodable};
+use rustc_serialize::json::Json;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
 
-use http_utils;
-use http_utils::HttpResponse;
+use http_utils::{HyperTransport, HyperTransportConfig};
+use transport::{self, HttpBody, HttpMethod, HttpRequest, HttpResponse, Transport};
+use csv::SolrCsvOptions;
 use document::SolrDocument;
 use query::SolrQuery;
-use request::SolrDeleteRequest;
+use auth::SolrCredentials;
+use retry::SolrRetryPolicy;
+use tls::SolrTlsConfig;
+use proxy::SolrProxyConfig;
+use request::{SolrDeleteRequest, SolrUpdateBatch, SolrUpdateOptions};
 use response::SolrError;
+use stream::DocumentStream;
+use xml::{ToXml, UpdateFormat};
 use response::{SolrPingResponse, SolrPingResult};
 use response::{SolrQueryResponse, SolrQueryResult};
 use response::{SolrUpdateResponse, SolrUpdateResult};
 
+// Queries with longer parameters are sent with POST by default,
+// keeping URLs well below the usual 8 KB limit of servers and proxies
+const DEFAULT_MAX_GET_QUERY_LENGTH: usize = 4096;
+
 /// Represents your API connection to Solr.
 /// You use this struct to perform operations on Solr.
+/// Requests are sent by a Transport, by default HyperTransport,
+/// which keeps connections alive and reuses them between requests.
+/// The client is cheap to clone and can be shared between threads;
+/// clones share the same transport.
+#[derive(Clone)]
 pub struct SolrClient {
     // Base URL to connect to Solr. Should include the core.
     // For example http://localhost:8983/solr/production/
     pub base_url: Url,
     select_url: Url,
+    json_query_url: Url,
+    get_url: Url,
     update_url: Url,
-    commit_url: Url,
-    rollback_url: Url,
-    optimize_url: Url,
-    pub ping_url: Url
+    pub ping_url: Url,
+    update_format: UpdateFormat,
+    retry_policy: Option<SolrRetryPolicy>,
+    credentials: Option<SolrCredentials>,
+    headers: Vec<(String, String)>,
+    max_get_query_length: Option<usize>,
+    transport: Arc<dyn Transport>
 }
 
 impl SolrClient {
@@ -38,9 +64,14 @@ impl SolrClient {
         url_parser.base_url(url).parse("./select").unwrap()
     }
 
-    fn build_commit_url(url: &Url) -> Url {
+    fn build_json_query_url(url: &Url) -> Url {
+        let mut url_parser = UrlParser::new();
+        url_parser.base_url(url).parse("./query").unwrap()
+    }
+
+    fn build_get_url(url: &Url) -> Url {
         let mut url_parser = UrlParser::new();
-        url_parser.base_url(url).parse("./update?commit=true").unwrap()
+        url_parser.base_url(url).parse("./get").unwrap()
     }
 
     fn build_ping_url(url: &Url) -> Url {
@@ -48,163 +79,680 @@ impl SolrClient {
         url_parser.base_url(url).parse("./admin/ping?wt=json").unwrap()
     }
 
-    fn build_rollback_url(url: &Url) -> Url {
-        let mut url_parser = UrlParser::new();
-        url_parser.base_url(url).parse("./update?rollback=true").unwrap()
+    /// Creates a new instance of Solr.
+    pub fn new(url: &Url) -> SolrClient {
+        SolrClientBuilder::new(url).build()
     }
 
-    fn build_optimize_url(url: &Url) -> Url {
-        let mut url_parser = UrlParser::new();
-        url_parser.base_url(url).parse("./update?optimize=true").unwrap()
+    /// Creates a builder to configure a new instance of Solr.
+    pub fn builder(url: &Url) -> SolrClientBuilder {
+        SolrClientBuilder::new(url)
     }
 
-    /// Creates a new instance of Solr.
-    pub fn new(url: &Url) -> SolrClient {
-        SolrClient {base_url: url.clone(),
-            select_url: SolrClient::build_select_url(url),
-            update_url: SolrClient::build_update_url(url),
-            commit_url: SolrClient::build_commit_url(url),
-            ping_url: SolrClient::build_ping_url(url),
-            rollback_url: SolrClient::build_rollback_url(url),
-            optimize_url: SolrClient::build_optimize_url(url)}
+    /// Returns a client of a core or collection under the base URL, e.g. `products`
+    /// for a client of `http://localhost:8983/solr/`. The returned client is cheap to create
+    /// and shares the transport, credentials, headers and retry policy of this one.
+    pub fn collection(&self, name: &str) -> SolrClient {
+        let url = UrlParser::new().base_url(&self.base_url).parse(&format!("./{}/", name)).unwrap();
+        self.with_base_url(url)
+    }
+
+    /// Sets the format of update request bodies, JSON by default
+    pub fn set_update_format(&mut self, format: UpdateFormat) {
+        self.update_format = format;
     }
 
     /// Issues a ping request to check if the server is alive.
     pub fn ping(&self) -> Result<SolrPingResponse, SolrError> {
-        let http_result = http_utils::get(&self.ping_url);
-        // TODO `
-        match http_result {
-            Ok(http_response) => match SolrPingResponse::from_json_str(&http_response.body) {
-                Ok(spr) => Ok(spr),
-                // TODO: insert actual builder_error inside solr_error
-                Err(err) => Err(SolrError{status: 0, time: 0, message: format!("Error parsing ping response JSON: {}", err.message)})
-            },
-            Err(_) => Err(SolrError{status: 0, time: 0, message: "Network error".to_string()})
-        }
+        let (http_result, attempts) = self.execute(true, &[], || ping_request(self));
+        with_attempts(handle_http_ping_result(http_result), attempts)
     }
 
     /// Performs Solr query
     pub fn query(&self, query: &SolrQuery) -> SolrQueryResult {
-        let mut query_url = self.select_url.clone();
-        query_url.set_query_from_pairs(query.to_pairs().iter().map(|&(ref x, ref y)| (&x[..], &y[..])));
-        let http_result = http_utils::get(&query_url);
-        handle_http_query_result(http_result)
+        let (http_result, attempts) = self.execute_query(query, &query.to_pairs());
+        with_attempts(handle_http_query_result(http_result), attempts)
+    }
+
+    /// Performs Solr query with the JSON Request API, posting it as a JSON body to `/query`,
+    /// or to the query's handler if set
+    pub fn query_json(&self, query: &SolrQuery) -> SolrQueryResult {
+        let body = query.to_json().to_string();
+        let (http_result, attempts) = self.execute(true, query.get_headers(), || json_query_request(self, query, &body));
+        with_attempts(handle_http_query_result(http_result), attempts)
+    }
+
+    /// Fetches the latest version of a document by its unique key with the real-time get handler
+    /// (`/get`), which also returns documents not committed yet
+    pub fn get_by_id(&self, id: &str) -> Result<Option<SolrDocument>, SolrError> {
+        self.get_by_ids(&[id]).map(|response| response.items.into_iter().next())
+    }
+
+    /// Fetches the latest versions of documents by their unique keys with the real-time get handler.
+    /// Documents which don't exist are left out.
+    pub fn get_by_ids(&self, ids: &[&str]) -> SolrQueryResult {
+        let (http_result, attempts) = self.execute(true, &[], || get_request(self, ids));
+        with_attempts(handle_http_query_result(http_result), attempts)
+    }
+
+    /// Performs Solr query, writing results in CSV format (wt=csv) to `writer`.
+    /// Returns the number of bytes written.
+    pub fn query_csv<W: Write>(&self, query: &SolrQuery, writer: &mut W) -> Result<u64, SolrError> {
+        let pairs: Vec<(String, String)> = query.to_pairs().into_iter()
+            .map(|(k, v)| if k == "wt" { (k, "csv".to_string()) } else { (k, v) })
+            .collect();
+        let (http_result, attempts) = self.execute_query(query, &pairs);
+        let result = match http_result.and_then(|mut response| {
+            if response.is_success() {
+                io::copy(&mut response.body, writer).map(Ok)
+            } else {
+                response.read_body().map(|body| Err(http_error(response.status, body)))
+            }
+        }) {
+            Ok(result) => result,
+            Err(err) => Err(transport_error(err))
+        };
+        with_attempts(result, attempts)
+    }
+
+    /// Sends a GET request to a handler at `path`, relative to the base URL, and returns its JSON response,
+    /// e.g. `admin/collections` with `action=CLUSTERSTATUS`
+    pub fn request_json(&self, path: &str, params: &[(&str, &str)]) -> Result<Json, SolrError> {
+        let mut url = UrlParser::new().base_url(&self.base_url).parse(path).unwrap();
+        url.set_query_from_pairs(Some(("wt", "json")).into_iter().chain(params.iter().cloned()));
+        let (http_result, attempts) = self.execute(true, &[], || HttpRequest::get(url.clone()));
+        let result = read_success_body(http_result).and_then(|body| match Json::from_str(&body) {
+            Ok(json) => Ok(json),
+            Err(err) => Err(SolrError::Parse { message: "Error parsing JSON response".to_string(), source: Some(Box::new(err)) })
+        });
+        with_attempts(result, attempts)
     }
 
-    // TODO DRY
     /// Adds new document to Solr, without committing
     pub fn add(&self, document: &SolrDocument) -> SolrUpdateResult {
-        self.add_many(&[document])
+        self.add_many_with_options(&[document], &SolrUpdateOptions::new())
+    }
+
+    /// Adds new document to Solr using given update options
+    pub fn add_with_options(&self, document: &SolrDocument, options: &SolrUpdateOptions) -> SolrUpdateResult {
+        self.add_many_with_options(&[document], options)
     }
 
     /// Adds new document to Solr and commits it
     pub fn add_and_commit(&self, document: &SolrDocument) -> SolrUpdateResult {
-        self.add_many_and_commit(&[document])
+        self.add_many_with_options(&[document], &SolrUpdateOptions::new().commit(true))
     }
 
     /// Adds multiple documents to Solr, without committing it
     pub fn add_many(&self, documents: &[&SolrDocument]) -> SolrUpdateResult {
-        let raw_json = json::encode(&documents);
-        match raw_json {
-            Ok(body) => {
-                let http_result =  http_utils::post_json(&self.update_url, &body);
-                handle_http_update_result(http_result)
-            },
-            Err(err) => Err(SolrError{status: 0, time: 0, message: "Error serialize solr document to json".to_string()})
-        }
+        self.add_many_with_options(documents, &SolrUpdateOptions::new())
     }
 
     /// Ads multiple documents to Solr and commits them
     pub fn add_many_and_commit(&self, documents: &[&SolrDocument]) -> SolrUpdateResult {
-        let raw_json = json::encode(&documents);
-        match raw_json {
-            Ok(body) => {
-                let http_result =  http_utils::post_json(&self.commit_url, &body);
-                handle_http_update_result(http_result)
-            },
-            Err(err) => Err(SolrError{status: 0, time: 0, message: "Error serialize solr document to json".to_string()})
-        }
+        self.add_many_with_options(documents, &SolrUpdateOptions::new().commit(true))
+    }
+
+    /// Adds multiple documents to Solr using given update options
+    pub fn add_many_with_options(&self, documents: &[&SolrDocument], options: &SolrUpdateOptions) -> SolrUpdateResult {
+        let body = try!(add_body(self, documents));
+        self.post_update_body(&body, options)
+    }
+
+    /// Adds documents from an iterator to Solr, without committing.
+    /// Documents are encoded while being sent, so memory use doesn't depend on their number.
+    /// Document boosts are only sent with `UpdateFormat::Xml`, as the JSON array doesn't hold them.
+    pub fn add_stream<I>(&self, documents: I) -> SolrUpdateResult
+        where I: Iterator<Item=SolrDocument> {
+        self.add_stream_with_options(documents, &SolrUpdateOptions::new())
+    }
+
+    /// Adds documents from an iterator to Solr using given update options
+    pub fn add_stream_with_options<I>(&self, documents: I, options: &SolrUpdateOptions) -> SolrUpdateResult
+        where I: Iterator<Item=SolrDocument> {
+        let mut stream = DocumentStream::new(documents, self.update_format);
+        let request = HttpRequest::post(self.build_update_url_with(options), self.update_content_type(), HttpBody::Stream(&mut stream))
+            .timeout(options.get_timeout());
+        handle_http_update_result(self.send(request, options.get_headers()))
+    }
+
+    /// Adds documents from CSV data to Solr, without committing
+    pub fn add_csv<R: Read>(&self, csv: R, csv_options: &SolrCsvOptions) -> SolrUpdateResult {
+        self.add_csv_with_options(csv, csv_options, &SolrUpdateOptions::new())
+    }
+
+    /// Adds documents from CSV data to Solr using given update options.
+    /// The data is streamed to Solr as it's read.
+    pub fn add_csv_with_options<R: Read>(&self, mut csv: R, csv_options: &SolrCsvOptions,
+                                         options: &SolrUpdateOptions) -> SolrUpdateResult {
+        let mut update_url = self.handler_url(options.get_handler(), &self.update_url);
+        let mut pairs = options.to_pairs();
+        pairs.extend(csv_options.to_pairs());
+        update_url.set_query_from_pairs(pairs.iter().map(|(x, y)| (&x[..], &y[..])));
+        let request = HttpRequest::post(update_url, "text/csv", HttpBody::Stream(&mut csv))
+            .timeout(options.get_timeout());
+        handle_http_update_result(self.send(request, options.get_headers()))
     }
 
     /// Performs an explicit commit, causing pending documents to be indexed
     pub fn commit(&self) -> SolrUpdateResult {
-        let http_result = http_utils::post_json(&self.commit_url, "");
-        handle_http_update_result(http_result)
+        self.commit_with_options(&SolrUpdateOptions::new())
+    }
+
+    /// Performs an explicit commit using given update options,
+    /// e.g. soft commit or commit with expunging deletes
+    pub fn commit_with_options(&self, options: &SolrUpdateOptions) -> SolrUpdateResult {
+        self.post_update_body("", &options.commit(true))
     }
 
     /// Performs a rollback of all non-committed documents
     pub fn rollback(&self) -> SolrUpdateResult {
-        let http_result = http_utils::post_json(&self.rollback_url, "");
-        handle_http_update_result(http_result)
+        self.rollback_with_options(&SolrUpdateOptions::new())
+    }
+
+    /// Performs a rollback using given update options, e.g. with headers or a timeout
+    pub fn rollback_with_options(&self, options: &SolrUpdateOptions) -> SolrUpdateResult {
+        let (http_result, attempts) = self.execute(false, options.get_headers(), || rollback_request(self, options));
+        with_attempts(handle_http_update_result(http_result), attempts)
     }
 
     /// Performs an explicit optimize, causing a merge of all segments to one.
     pub fn optimize(&self) -> SolrUpdateResult {
-        let http_result = http_utils::post_json(&self.optimize_url, "");
-        handle_http_update_result(http_result)
+        self.optimize_with_options(&SolrUpdateOptions::new())
+    }
+
+    /// Performs an explicit optimize using given update options,
+    /// e.g. merging down to `max_segments` instead of one
+    pub fn optimize_with_options(&self, options: &SolrUpdateOptions) -> SolrUpdateResult {
+        self.post_update_body("", &options.optimize(true))
     }
 
     /// Deletes a single document by a unique ID
     pub fn delete_by_id(&self, id: &str) -> SolrUpdateResult {
-        let delete_request = SolrDeleteRequest::from_id(id);
-        let raw_json = json::encode(&delete_request);
-        match raw_json {
-            Ok(body) => {
-                let http_result =  http_utils::post_json(&self.commit_url, &body);
-                handle_http_update_result(http_result)
-            },
-            Err(err) => Err(SolrError{status: 0, time: 0, message: "Error serialize solr document to json".to_string()})
-        }
+        self.delete_by_id_with_options(id, &SolrUpdateOptions::new().commit(true))
+    }
+
+    /// Deletes a single document by a unique ID using given update options
+    pub fn delete_by_id_with_options(&self, id: &str, options: &SolrUpdateOptions) -> SolrUpdateResult {
+        self.post_update(&SolrDeleteRequest::from_id(id), options)
     }
 
     /// Deletes a list of documents by IDs
     pub fn delete_by_ids(&self, ids: &Vec<String>) -> SolrUpdateResult {
-        let delete_request = SolrDeleteRequest::from_ids(&ids);
-        let raw_json = json::encode(&delete_request);
-        match raw_json {
-            Ok(body) => {
-                let http_result =  http_utils::post_json(&self.commit_url, &body);
-                handle_http_update_result(http_result)
-            },
-            Err(err) => Err(SolrError{status: 0, time: 0, message: "Error serialize solr document to json".to_string()})
-        }
+        self.delete_by_ids_with_options(ids, &SolrUpdateOptions::new().commit(true))
+    }
+
+    /// Deletes a list of documents by IDs using given update options
+    pub fn delete_by_ids_with_options(&self, ids: &Vec<String>, options: &SolrUpdateOptions) -> SolrUpdateResult {
+        self.post_update(&SolrDeleteRequest::from_ids(ids), options)
     }
 
     /// Deletes documents from the index by query
     pub fn delete_by_query(&self, query: &str) -> SolrUpdateResult {
-        let delete_request = SolrDeleteRequest::from_query(query);
-        let raw_json = json::encode(&delete_request);
-        match raw_json {
-            Ok(body) => {
-                let http_result =  http_utils::post_json(&self.commit_url, &body);
-                handle_http_update_result(http_result)
-            },
-            Err(err) => Err(SolrError{status: 0, time: 0, message: "Error serialize solr document to json".to_string()})
+        self.delete_by_query_with_options(query, &SolrUpdateOptions::new().commit(true))
+    }
+
+    /// Deletes documents from the index by query using given update options
+    pub fn delete_by_query_with_options(&self, query: &str, options: &SolrUpdateOptions) -> SolrUpdateResult {
+        self.post_update(&SolrDeleteRequest::from_query(query), options)
+    }
+
+    /// Sends a batch of add, delete, commit and optimize commands in a single request
+    pub fn update_batch(&self, batch: &SolrUpdateBatch) -> SolrUpdateResult {
+        self.update_batch_with_options(batch, &SolrUpdateOptions::new())
+    }
+
+    /// Sends a batch of update commands in a single request using given update options
+    pub fn update_batch_with_options(&self, batch: &SolrUpdateBatch, options: &SolrUpdateOptions) -> SolrUpdateResult {
+        self.post_update(batch, options)
+    }
+
+    fn post_update<T: Encodable + ToXml>(&self, data: &T, options: &SolrUpdateOptions) -> SolrUpdateResult {
+        let body = try!(update_body(self, data));
+        self.post_update_body(&body, options)
+    }
+
+    fn post_update_body(&self, body: &str, options: &SolrUpdateOptions) -> SolrUpdateResult {
+        let (http_result, attempts) = self.execute(false, options.get_headers(), || update_request(self, body, options));
+        with_attempts(handle_http_update_result(http_result), attempts)
+    }
+
+    fn execute_query(&self, query: &SolrQuery, pairs: &[(String, String)]) -> (io::Result<HttpResponse>, u32) {
+        let params = query_params(pairs);
+        self.execute(true, query.get_headers(), || query_request(self, query, &params))
+    }
+
+    // Sends a request, retrying it according to the retry policy.
+    // Requests which aren't idempotent are retried only when the policy allows retrying updates.
+    // Returns the last result and the number of attempts.
+    fn execute<'a, F>(&self, idempotent: bool, headers: &[(String, String)], request: F) -> (io::Result<HttpResponse>, u32)
+        where F: Fn() -> HttpRequest<'a> {
+        let policy = match retry_policy(self, idempotent) {
+            Some(policy) => policy,
+            None => return (self.send(request(), headers), 1)
+        };
+        let mut attempt = 1;
+        loop {
+            let result = self.send(request(), headers);
+            if attempt >= policy.get_max_attempts() || !policy.should_retry(&result) {
+                return (result, attempt);
+            }
+            thread::sleep(policy.backoff(attempt));
+            attempt += 1;
         }
     }
+
+    // Sends a request with credentials, default headers and per-request headers,
+    // the latter replacing default headers with the same name
+    fn send(&self, request: HttpRequest, headers: &[(String, String)]) -> io::Result<HttpResponse> {
+        self.transport.execute(with_headers(self, request, headers))
+    }
+
+    fn with_base_url(&self, url: Url) -> SolrClient {
+        let mut client = self.clone();
+        client.select_url = SolrClient::build_select_url(&url);
+        client.json_query_url = SolrClient::build_json_query_url(&url);
+        client.get_url = SolrClient::build_get_url(&url);
+        client.update_url = SolrClient::build_update_url(&url);
+        client.ping_url = SolrClient::build_ping_url(&url);
+        client.base_url = url;
+        client
+    }
+
+    // URL of a custom handler relative to the base URL, or the default one
+    fn handler_url(&self, handler: Option<&str>, default: &Url) -> Url {
+        match handler {
+            Some(path) => UrlParser::new().base_url(&self.base_url).parse(&format!("./{}", path.trim_start_matches('/'))).unwrap(),
+            None => default.clone()
+        }
+    }
+
+    fn update_content_type(&self) -> &'static str {
+        match self.update_format {
+            UpdateFormat::Json => "application/json",
+            UpdateFormat::Xml => "application/xml"
+        }
+    }
+
+    fn build_update_url_with(&self, options: &SolrUpdateOptions) -> Url {
+        let mut update_url = self.handler_url(options.get_handler(), &self.update_url);
+        update_url.set_query_from_pairs(options.to_pairs().iter().map(|(x, y)| (&x[..], &y[..])));
+        update_url
+    }
 }
 
-fn handle_http_update_result(http_result: Result<HttpResponse, Error>) -> SolrUpdateResult {
-    match http_result {
-        Ok(response) => {
-            match json::decode::<SolrUpdateResponse>(&response.body) {
-                Ok(sur) => Ok(sur),
-                Err(err) => Err(SolrError{status: 0, time: 0, message: format!("Parse error: {}", err)})
-            }
-        },
-        Err(err) => Err(SolrError{status: 0, time: 0, message: format!("Http error: {}", err)})
+/// Builds SolrClient with non-default configuration: connection pool, timeouts, TLS, proxy
+/// and compression of the default transport, update format, credentials, default headers,
+/// retry policy and the length above which queries are POSTed.
+/// Each setter returns a modified copy, so a builder can be reused for several clients.
+#[derive(Clone)]
+pub struct SolrClientBuilder {
+    base_url: Url,
+    transport_config: HyperTransportConfig,
+    update_format: UpdateFormat,
+    retry_policy: Option<SolrRetryPolicy>,
+    credentials: Option<SolrCredentials>,
+    headers: Vec<(String, String)>,
+    max_get_query_length: Option<usize>,
+    transport: Option<Arc<dyn Transport>>
+}
+
+impl SolrClientBuilder {
+    /// Creates a builder for a given base URL, which should include the core.
+    pub fn new(url: &Url) -> SolrClientBuilder {
+        SolrClientBuilder {
+            base_url: url.clone(),
+            transport_config: HyperTransportConfig::default(),
+            update_format: UpdateFormat::Json,
+            retry_policy: None,
+            credentials: None,
+            headers: Vec::new(),
+            max_get_query_length: Some(DEFAULT_MAX_GET_QUERY_LENGTH),
+            transport: None
+        }
+    }
+
+    /// Sets the base URL of built clients, keeping the rest of the configuration
+    pub fn base_url(&self, url: &Url) -> SolrClientBuilder {
+        let mut builder = self.clone();
+        builder.base_url = url.clone();
+        builder
+    }
+
+    /// Creates the transport now, so that all clients built by the returned builder share it
+    /// and its connection pool, e.g. clients of different nodes or cores
+    pub fn share_transport(&self) -> SolrClientBuilder {
+        let mut builder = self.clone();
+        if builder.transport.is_none() {
+            builder.transport = Some(Arc::new(HyperTransport::new(self.transport_config.clone())));
+        }
+        builder
+    }
+
+    /// Sets the maximum number of idle connections kept alive per host, 5 by default
+    pub fn max_idle_connections(&self, max_idle_connections: usize) -> SolrClientBuilder {
+        let mut builder = self.clone();
+        builder.transport_config.max_idle_connections = max_idle_connections;
+        builder
+    }
+
+    /// Sets how long an idle connection may be reused.
+    /// By default idle connections are kept until the server closes them.
+    pub fn idle_timeout(&self, idle_timeout: Duration) -> SolrClientBuilder {
+        let mut builder = self.clone();
+        builder.transport_config.idle_timeout = Some(idle_timeout);
+        builder
+    }
+
+    /// Sets the maximum time to establish a connection. There's no timeout by default.
+    pub fn connect_timeout(&self, timeout: Duration) -> SolrClientBuilder {
+        let mut builder = self.clone();
+        builder.transport_config.connect_timeout = Some(timeout);
+        builder
+    }
+
+    /// Sets the maximum time to wait for response data. There's no timeout by default.
+    /// It can be overridden per request with `SolrQuery::timeout` or `SolrUpdateOptions::timeout`.
+    pub fn read_timeout(&self, timeout: Duration) -> SolrClientBuilder {
+        let mut builder = self.clone();
+        builder.transport_config.read_timeout = Some(timeout);
+        builder
+    }
+
+    /// Sets the maximum time to wait while sending request data. There's no timeout by default.
+    pub fn write_timeout(&self, timeout: Duration) -> SolrClientBuilder {
+        let mut builder = self.clone();
+        builder.transport_config.write_timeout = Some(timeout);
+        builder
+    }
+
+    /// Sets the format of update request bodies, JSON by default
+    pub fn update_format(&self, update_format: UpdateFormat) -> SolrClientBuilder {
+        let mut builder = self.clone();
+        builder.update_format = update_format;
+        builder
+    }
+
+    /// Sets the policy for retrying failed requests. Requests aren't retried by default.
+    pub fn retry_policy(&self, retry_policy: SolrRetryPolicy) -> SolrClientBuilder {
+        let mut builder = self.clone();
+        builder.retry_policy = Some(retry_policy);
+        builder
+    }
+
+    /// Sets TLS settings for https base URLs, e.g. a custom CA file or a client certificate
+    pub fn tls(&self, tls: SolrTlsConfig) -> SolrClientBuilder {
+        let mut builder = self.clone();
+        builder.transport_config.tls = tls;
+        builder
+    }
+
+    /// Sends requests through an HTTP proxy, except to hosts in its no-proxy list
+    pub fn proxy(&self, proxy: SolrProxyConfig) -> SolrClientBuilder {
+        let mut builder = self.clone();
+        builder.transport_config.proxy = Some(proxy);
+        builder
+    }
+
+    /// Sets whether gzip and deflate compressed responses are accepted, which they are by default
+    pub fn response_compression(&self, enabled: bool) -> SolrClientBuilder {
+        let mut builder = self.clone();
+        builder.transport_config.response_compression = enabled;
+        builder
+    }
+
+    /// Compresses update bodies of at least `min_size` bytes with gzip.
+    /// Streamed updates, e.g. by `add_stream`, aren't compressed.
+    pub fn request_compression(&self, min_size: usize) -> SolrClientBuilder {
+        let mut builder = self.clone();
+        builder.transport_config.request_compression = Some(min_size);
+        builder
+    }
+
+    /// Sets credentials for HTTP Basic authentication, sent with every request
+    pub fn basic_auth(&self, username: &str, password: &str) -> SolrClientBuilder {
+        let mut builder = self.clone();
+        builder.credentials = Some(SolrCredentials::new(username, password));
+        builder
+    }
+
+    /// Adds an HTTP header sent with every request,
+    /// e.g. an API key required by a gateway in front of Solr
+    pub fn default_header(&self, name: &str, value: &str) -> SolrClientBuilder {
+        let mut builder = self.clone();
+        builder.headers.push((name.to_string(), value.to_string()));
+        builder
+    }
+
+    /// Sets the maximum length of encoded query parameters sent in the URL, 4096 bytes by default.
+    /// Longer queries, e.g. with filters on hundreds of ids, are sent with POST as a form,
+    /// unless the query sets its HTTP method.
+    pub fn max_get_query_length(&self, max_length: usize) -> SolrClientBuilder {
+        let mut builder = self.clone();
+        builder.max_get_query_length = Some(max_length);
+        builder
+    }
+
+    /// Sends all queries with GET, unless the query sets its HTTP method
+    pub fn always_get_queries(&self) -> SolrClientBuilder {
+        let mut builder = self.clone();
+        builder.max_get_query_length = None;
+        builder
+    }
+
+    /// Sets the transport sending HTTP requests, e.g. RecordingTransport in tests.
+    /// Connection pool and timeout settings only apply to the default HyperTransport.
+    pub fn transport<T: Transport + 'static>(&self, transport: T) -> SolrClientBuilder {
+        let mut builder = self.clone();
+        builder.transport = Some(Arc::new(transport));
+        builder
+    }
+
+    /// Creates SolrClient with this configuration
+    pub fn build(&self) -> SolrClient {
+        let url = &self.base_url;
+        let transport = match self.transport {
+            Some(ref transport) => transport.clone(),
+            None => Arc::new(HyperTransport::new(self.transport_config.clone()))
+        };
+        SolrClient {base_url: url.clone(),
+            select_url: SolrClient::build_select_url(url),
+            json_query_url: SolrClient::build_json_query_url(url),
+            get_url: SolrClient::build_get_url(url),
+            update_url: SolrClient::build_update_url(url),
+            ping_url: SolrClient::build_ping_url(url),
+            update_format: self.update_format,
+            retry_policy: self.retry_policy.clone(),
+            credentials: self.credentials.clone(),
+            headers: self.headers.clone(),
+            max_get_query_length: self.max_get_query_length,
+            transport}
     }
 }
 
-// TODO add handling http error
-fn handle_http_query_result(http_result: Result<HttpResponse, Error>) -> SolrQueryResult {
-    match http_result {
-        Ok(response) => {
-            match SolrQueryResponse::from_json_str(&response.body) {
-                Ok(qp) => Ok(qp),
-                Err(err) => Err(err)
-            }
+// Building requests and handling responses is shared with SolrAsyncClient.
+// These are functions rather than methods to keep them out of SolrClient's public API.
+
+/// Request of a ping
+pub fn ping_request<'a>(client: &SolrClient) -> HttpRequest<'a> {
+    HttpRequest::get(client.ping_url.clone())
+}
+
+/// Request of a real-time get of documents by their unique keys
+pub fn get_request<'a>(client: &SolrClient, ids: &[&str]) -> HttpRequest<'a> {
+    // Solr splits ids on commas, unless escaped
+    let ids: Vec<String> = ids.iter().map(|id| id.replace('\\', "\\\\").replace(',', "\\,")).collect();
+    let mut get_url = client.get_url.clone();
+    get_url.set_query_from_pairs(&[("wt", "json"), ("omitHeader", "false"), ("ids", &ids.join(","))]);
+    HttpRequest::get(get_url)
+}
+
+/// Encodes query parameters for `query_request`
+pub fn query_params(pairs: &[(String, String)]) -> String {
+    form_urlencoded::serialize(pairs.iter().map(|(x, y)| (&x[..], &y[..])))
+}
+
+/// Request of a query, with parameters in the URL, or in a form body when the query asks for POST
+/// or they are too long for the URL
+pub fn query_request<'a>(client: &SolrClient, query: &SolrQuery, params: &'a str) -> HttpRequest<'a> {
+    let url = client.handler_url(query.get_handler(), &client.select_url);
+    let method = query.get_method().unwrap_or(match client.max_get_query_length {
+        Some(max_length) if params.len() > max_length => HttpMethod::Post,
+        _ => HttpMethod::Get
+    });
+    let request = match method {
+        HttpMethod::Get => {
+            let mut query_url = url;
+            query_url.query = Some(params.to_string());
+            HttpRequest::get(query_url)
         },
-        Err(err) => Err(SolrError{status: 0, time: 0, message: format!("Http error: {}", err)})
+        HttpMethod::Post => HttpRequest::post(url, "application/x-www-form-urlencoded", HttpBody::Text(params))
+    };
+    request.timeout(query.get_timeout())
+}
+
+/// Request of a query with the JSON Request API, `body` being the query's JSON
+pub fn json_query_request<'a>(client: &SolrClient, query: &SolrQuery, body: &'a str) -> HttpRequest<'a> {
+    let url = client.handler_url(query.get_handler(), &client.json_query_url);
+    HttpRequest::post(url, "application/json", HttpBody::Text(body)).timeout(query.get_timeout())
+}
+
+/// Encodes update commands in the client's update format
+pub fn update_body<T: Encodable + ToXml>(client: &SolrClient, data: &T) -> Result<String, SolrError> {
+    match client.update_format {
+        UpdateFormat::Json => json::encode(data).map_err(SolrError::Serialization),
+        UpdateFormat::Xml => Ok(data.to_xml())
+    }
+}
+
+/// Encodes documents to add in the client's update format
+pub fn add_body(client: &SolrClient, documents: &[&SolrDocument]) -> Result<String, SolrError> {
+    // A JSON array of documents can't carry document boosts, add commands can
+    if client.update_format == UpdateFormat::Json && documents.iter().any(|document| document.boost().is_some()) {
+        let mut batch = SolrUpdateBatch::new();
+        batch.add_many(documents);
+        return update_body(client, &batch);
+    }
+    update_body(client, &documents)
+}
+
+/// Request posting an encoded update body
+pub fn update_request<'a>(client: &SolrClient, body: &'a str, options: &SolrUpdateOptions) -> HttpRequest<'a> {
+    HttpRequest::post(client.build_update_url_with(options), client.update_content_type(), HttpBody::Text(body))
+        .timeout(options.get_timeout())
+}
+
+/// Request of a rollback
+pub fn rollback_request<'a>(client: &SolrClient, options: &SolrUpdateOptions) -> HttpRequest<'a> {
+    let mut rollback_url = client.handler_url(options.get_handler(), &client.update_url);
+    let mut pairs = options.to_pairs();
+    pairs.push(("rollback".to_string(), "true".to_string()));
+    rollback_url.set_query_from_pairs(pairs.iter().map(|(x, y)| (&x[..], &y[..])));
+    HttpRequest::post(rollback_url, client.update_content_type(), HttpBody::Text(""))
+        .timeout(options.get_timeout())
+}
+
+/// Adds credentials, default headers and per-request headers to a request,
+/// the latter replacing default headers with the same name
+pub fn with_headers<'a>(client: &SolrClient, mut request: HttpRequest<'a>, headers: &[(String, String)]) -> HttpRequest<'a> {
+    if let Some(ref credentials) = client.credentials {
+        request.set_header("Authorization", &credentials.authorization());
+    }
+    for (name, value) in client.headers.iter().chain(headers) {
+        request.set_header(name, value);
+    }
+    request
+}
+
+/// Returns the settings of the default transport of clients built by `builder`
+pub fn transport_config(builder: &SolrClientBuilder) -> &HyperTransportConfig {
+    &builder.transport_config
+}
+
+/// Returns the retry policy of a request. Requests which aren't idempotent
+/// are retried only when the policy allows retrying updates.
+pub fn retry_policy(client: &SolrClient, idempotent: bool) -> Option<&SolrRetryPolicy> {
+    client.retry_policy.as_ref().filter(|policy| idempotent || policy.retries_updates())
+}
+
+pub fn handle_http_ping_result(http_result: io::Result<HttpResponse>) -> Result<SolrPingResponse, SolrError> {
+    read_success_body(http_result).and_then(|body| match SolrPingResponse::from_json_str(&body) {
+        Ok(spr) => Ok(spr),
+        Err(err) => Err(SolrError::Parse { message: "Error parsing ping response JSON".to_string(), source: Some(Box::new(err)) })
+    })
+}
+
+pub fn handle_http_update_result(http_result: io::Result<HttpResponse>) -> SolrUpdateResult {
+    read_success_body(http_result).and_then(|body| match json::decode::<SolrUpdateResponse>(&body) {
+        Ok(sur) => Ok(sur),
+        Err(err) => Err(SolrError::Parse { message: "Parse error".to_string(), source: Some(Box::new(err)) })
+    })
+}
+
+pub fn handle_http_query_result(http_result: io::Result<HttpResponse>) -> SolrQueryResult {
+    read_success_body(http_result).and_then(|body| SolrQueryResponse::from_json_str(&body))
+}
+
+// Returns the body of a successful response, or the error an unsuccessful one reports
+fn read_success_body(http_result: io::Result<HttpResponse>) -> Result<String, SolrError> {
+    let mut response = try!(http_result.map_err(transport_error));
+    let body = try!(response.read_body().map_err(transport_error));
+    if response.is_success() {
+        Ok(body)
+    } else {
+        Err(http_error(response.status, body))
+    }
+}
+
+// Decodes the error reported by Solr, which may be missing, e.g. when a proxy answered
+fn http_error(status: u16, body: String) -> SolrError {
+    match json::decode::<SolrError>(&body) {
+        Ok(SolrError::Solr { code, msg, metadata, trace, attempts, .. }) =>
+            SolrError::Solr { status, code, msg, metadata, trace, attempts },
+        _ => SolrError::Http { status, body, attempts: 1 }
+    }
+}
+
+// Header values may hold secrets like API keys, so only their names are printed
+fn header_names(headers: &[(String, String)]) -> Vec<&str> {
+    headers.iter().map(|header| &header.0[..]).collect()
+}
+
+impl fmt::Debug for SolrClient {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SolrClient")
+            .field("base_url", &self.base_url.serialize())
+            .field("update_format", &self.update_format)
+            .field("retry_policy", &self.retry_policy)
+            .field("credentials", &self.credentials)
+            .field("headers", &header_names(&self.headers))
+            .field("max_get_query_length", &self.max_get_query_length)
+            .finish()
+    }
+}
+
+impl fmt::Debug for SolrClientBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SolrClientBuilder")
+            .field("base_url", &self.base_url.serialize())
+            .field("transport_config", &self.transport_config)
+            .field("update_format", &self.update_format)
+            .field("retry_policy", &self.retry_policy)
+            .field("credentials", &self.credentials)
+            .field("headers", &header_names(&self.headers))
+            .field("max_get_query_length", &self.max_get_query_length)
+            .finish()
+    }
+}
+
+pub fn with_attempts<T>(result: Result<T, SolrError>, attempts: u32) -> Result<T, SolrError> {
+    result.map_err(|err| err.with_attempts(attempts))
+}
+
+fn transport_error(err: io::Error) -> SolrError {
+    if transport::is_timeout(&err) {
+        SolrError::Timeout { connect: transport::is_connect_timeout(&err), source: err, attempts: 1 }
+    } else {
+        SolrError::Transport { source: err, attempts: 1 }
     }
 }
@@ -0,0 +1,318 @@
+pub use self::state::{SolrClusterState, SolrReplica, SolrShard};
+
+mod state;
+
+use std::cmp;
+use std::slice;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use url::Url;
+
+use client::{SolrClient, SolrClientBuilder};
+use document::SolrDocument;
+use query::SolrQuery;
+use request::SolrUpdateOptions;
+use response::{SolrError, SolrQueryResult, SolrUpdateResponse, SolrUpdateResult};
+use router::SolrCompositeIdRouter;
+
+/// Client of a SolrCloud collection, routing requests by the cluster state.
+/// The state is read with CLUSTERSTATUS from the Collections API of any node and cached.
+/// Queries are sent to active replicas in turn, and documents to leaders of the shards
+/// they belong to. The state is refreshed when a request fails in a way suggesting it's stale,
+/// and the request is sent once more. Updates split across shards are sent to every shard,
+/// and only requests which failed are sent again; when some still fail, `SolrError::Partial`
+/// tells which ones.
+/// Clones share the cached state and the transport.
+#[derive(Clone)]
+pub struct SolrCloudClient {
+    collection: String,
+    router: SolrCompositeIdRouter,
+    // Nodes to read the cluster state from, e.g. http://localhost:8983/solr/
+    nodes: Vec<Url>,
+    builder: SolrClientBuilder,
+    state: Arc<Mutex<Option<Arc<SolrClusterState>>>>,
+    next: Arc<AtomicUsize>
+}
+
+impl SolrCloudClient {
+    /// Creates a client of a collection, reading the cluster state from a node,
+    /// e.g. `http://localhost:8983/solr/`
+    pub fn new(node_url: &Url, collection: &str) -> SolrCloudClient {
+        SolrCloudClient::with_builder(&SolrClient::builder(node_url), slice::from_ref(node_url), collection)
+    }
+
+    /// Creates a client of a collection, reading the cluster state from any of given nodes.
+    /// Clients of all nodes are built with the configuration of `builder` and share its transport.
+    pub fn with_builder(builder: &SolrClientBuilder, node_urls: &[Url], collection: &str) -> SolrCloudClient {
+        SolrCloudClient {
+            collection: collection.to_string(),
+            router: SolrCompositeIdRouter::new(),
+            nodes: node_urls.to_vec(),
+            builder: builder.share_transport(),
+            state: Arc::new(Mutex::new(None)),
+            next: Arc::new(AtomicUsize::new(0))
+        }
+    }
+
+    /// Sets the unique key field used to route documents, `id` by default
+    pub fn id_field(&self, id_field: &str) -> SolrCloudClient {
+        let mut client = self.clone();
+        client.router = self.router.id_field(id_field);
+        client
+    }
+
+    /// Returns the cached cluster state, reading it first if needed
+    pub fn cluster_state(&self) -> Result<Arc<SolrClusterState>, SolrError> {
+        let cached = self.state.lock().unwrap().clone();
+        match cached {
+            Some(state) => Ok(state),
+            None => self.refresh()
+        }
+    }
+
+    /// Reads the cluster state from the first node answering, trying nodes of the cached state
+    /// after the configured ones
+    pub fn refresh(&self) -> Result<Arc<SolrClusterState>, SolrError> {
+        let mut nodes = self.nodes.clone();
+        if let Some(ref state) = *self.state.lock().unwrap() {
+            for replica in state.shards.iter().flat_map(|shard| shard.replicas.iter()) {
+                if let Ok(url) = Url::parse(&format!("{}/", replica.base_url.trim_end_matches('/'))) {
+                    if !nodes.contains(&url) {
+                        nodes.push(url);
+                    }
+                }
+            }
+        }
+        let mut last_error = SolrError::Unavailable("No SolrCloud nodes configured".to_string());
+        for node in nodes {
+            let params = [("action", "CLUSTERSTATUS"), ("collection", &self.collection[..])];
+            match self.client(&node).request_json("admin/collections", &params) {
+                Ok(json) => {
+                    let state = Arc::new(try!(SolrClusterState::from_json(&json, &self.collection)));
+                    *self.state.lock().unwrap() = Some(state.clone());
+                    return Ok(state);
+                },
+                Err(err) => last_error = err
+            }
+        }
+        Err(last_error)
+    }
+
+    /// Performs Solr query on the next active replica
+    pub fn query(&self, query: &SolrQuery) -> SolrQueryResult {
+        self.with_state(|state| {
+            let replicas = state.active_replicas();
+            if replicas.is_empty() {
+                return Err(SolrError::Unavailable(format!("No active replicas of {}", state.collection)));
+            }
+            let replica = replicas[self.next.fetch_add(1, Ordering::Relaxed) % replicas.len()];
+            self.client(&try!(state.collection_url(replica))).query(query)
+        })
+    }
+
+    /// Adds new document to Solr, without committing
+    pub fn add(&self, document: &SolrDocument) -> SolrUpdateResult {
+        self.add_many_with_options(&[document], &SolrUpdateOptions::new())
+    }
+
+    /// Adds new document to Solr, with given update options
+    pub fn add_with_options(&self, document: &SolrDocument, options: &SolrUpdateOptions) -> SolrUpdateResult {
+        self.add_many_with_options(&[document], options)
+    }
+
+    /// Adds multiple documents to Solr, without committing
+    pub fn add_many(&self, documents: &[&SolrDocument]) -> SolrUpdateResult {
+        self.add_many_with_options(documents, &SolrUpdateOptions::new())
+    }
+
+    /// Adds multiple documents to Solr with given update options,
+    /// sending a request to the leader of each shard the documents belong to
+    pub fn add_many_with_options(&self, documents: &[&SolrDocument], options: &SolrUpdateOptions) -> SolrUpdateResult {
+        self.update_by_leader(documents, |document| self.router.document_id(document), |client, documents| {
+            client.add_many_with_options(&documents.iter().map(|document| **document).collect::<Vec<_>>(), options)
+        })
+    }
+
+    /// Deletes a single document by a unique ID
+    pub fn delete_by_id(&self, id: &str) -> SolrUpdateResult {
+        self.delete_by_id_with_options(id, &SolrUpdateOptions::new().commit(true))
+    }
+
+    /// Deletes a single document by a unique ID using given update options
+    pub fn delete_by_id_with_options(&self, id: &str, options: &SolrUpdateOptions) -> SolrUpdateResult {
+        self.delete_by_ids_with_options(&[id.to_string()], options)
+    }
+
+    /// Deletes a list of documents by IDs
+    pub fn delete_by_ids(&self, ids: &[String]) -> SolrUpdateResult {
+        self.delete_by_ids_with_options(ids, &SolrUpdateOptions::new().commit(true))
+    }
+
+    /// Deletes a list of documents by IDs using given update options,
+    /// sending a request to the leader of each shard the documents belong to
+    pub fn delete_by_ids_with_options(&self, ids: &[String], options: &SolrUpdateOptions) -> SolrUpdateResult {
+        self.update_by_leader(ids, |id| Some(&id[..]), |client, ids| {
+            client.delete_by_ids_with_options(&ids.iter().map(|id| id.to_string()).collect(), options)
+        })
+    }
+
+    /// Deletes documents from all shards by query
+    pub fn delete_by_query(&self, query: &str) -> SolrUpdateResult {
+        self.delete_by_query_with_options(query, &SolrUpdateOptions::new().commit(true))
+    }
+
+    /// Deletes documents from all shards by query using given update options
+    pub fn delete_by_query_with_options(&self, query: &str, options: &SolrUpdateOptions) -> SolrUpdateResult {
+        self.with_collection(|client| client.delete_by_query_with_options(query, options))
+    }
+
+    /// Performs an explicit commit on all shards
+    pub fn commit(&self) -> SolrUpdateResult {
+        self.commit_with_options(&SolrUpdateOptions::new())
+    }
+
+    /// Performs a commit on all shards using given update options
+    pub fn commit_with_options(&self, options: &SolrUpdateOptions) -> SolrUpdateResult {
+        self.with_collection(|client| client.commit_with_options(options))
+    }
+
+    /// Performs an explicit optimize on all shards
+    pub fn optimize(&self) -> SolrUpdateResult {
+        self.optimize_with_options(&SolrUpdateOptions::new())
+    }
+
+    /// Performs an optimize on all shards using given update options
+    pub fn optimize_with_options(&self, options: &SolrUpdateOptions) -> SolrUpdateResult {
+        self.with_collection(|client| client.optimize_with_options(options))
+    }
+
+    fn client(&self, url: &Url) -> SolrClient {
+        self.builder.base_url(url).build()
+    }
+
+    // Runs a request with the cached state, once more with a refreshed state if it seems stale
+    fn with_state<T, F>(&self, request: F) -> Result<T, SolrError> where F: Fn(&SolrClusterState) -> Result<T, SolrError> {
+        let state = try!(self.cluster_state());
+        match request(&state) {
+            Err(ref err) if is_stale_state(err) => {
+                let state = try!(self.refresh());
+                request(&state)
+            },
+            result => result
+        }
+    }
+
+    // Sends a collection-wide request to an active replica, which distributes it to all shards
+    fn with_collection<F>(&self, request: F) -> SolrUpdateResult where F: Fn(&SolrClient) -> SolrUpdateResult {
+        self.with_state(|state| match state.active_replicas().first() {
+            Some(replica) => request(&self.client(&try!(state.collection_url(replica)))),
+            None => Err(SolrError::Unavailable(format!("No active replicas of {}", state.collection)))
+        })
+    }
+
+    // Sends items in a request to each shard leader they are routed to. Every group is sent,
+    // and only items which couldn't be routed or groups failing because the state seems stale
+    // are routed and sent again with a refreshed state, so that updates applied by other shards
+    // aren't repeated.
+    fn update_by_leader<'a, T, I, S>(&self, items: &'a [T], id: I, send: S) -> SolrUpdateResult
+        where I: Fn(&'a T) -> Option<&'a str>, S: Fn(&SolrClient, &[&'a T]) -> SolrUpdateResult {
+        let mut state = try!(self.cluster_state());
+        let mut pending: Vec<&'a T> = items.iter().collect();
+        let mut refreshed = false;
+        let mut sent = 0;
+        let mut time = 0;
+        let mut failures = Vec::new();
+        while !pending.is_empty() {
+            let mut stale = Vec::new();
+            let (groups, unroutable) = self.group_by_leader(&state, &pending, &id);
+            for (item, err) in unroutable {
+                if !refreshed && is_stale_state(&err) {
+                    stale.push(item);
+                } else {
+                    failures.push((id(item).unwrap_or(&self.collection).to_string(), err));
+                }
+            }
+            for (url, group) in groups {
+                match send(&self.client(&url), &group) {
+                    Ok(response) => {
+                        sent += 1;
+                        time = cmp::max(time, response.time);
+                    },
+                    Err(ref err) if !refreshed && is_stale_state(err) => stale.extend(group),
+                    Err(err) => failures.push((url.serialize(), err))
+                }
+            }
+            pending = stale;
+            if !pending.is_empty() {
+                refreshed = true;
+                match self.refresh() {
+                    Ok(refreshed_state) => state = refreshed_state,
+                    Err(err) => {
+                        failures.push((self.collection.clone(), err));
+                        break;
+                    }
+                }
+            }
+        }
+        if failures.is_empty() {
+            Ok(SolrUpdateResponse { status: 0, time })
+        } else if sent == 0 {
+            // Nothing was applied, so the update failed as a whole
+            Err(failures.remove(0).1)
+        } else {
+            Err(SolrError::Partial { failures })
+        }
+    }
+
+    // Groups items by the URL of the shard leader they are routed to, in order of first appearance.
+    // Items without an id, or of collections not using hash ranges, go to any active replica.
+    // Items which can't be routed are returned apart, with the reason.
+    fn group_by_leader<'a, T, F>(&self, state: &SolrClusterState, items: &[&'a T], id: F)
+                                 -> (LeaderGroups<'a, T>, Vec<(&'a T, SolrError)>)
+        where F: Fn(&'a T) -> Option<&'a str> {
+        let mut groups: Vec<(Url, Vec<&T>)> = Vec::new();
+        let mut unroutable = Vec::new();
+        for &item in items {
+            let url = match self.leader_url(state, id(item)) {
+                Ok(url) => url,
+                Err(err) => {
+                    unroutable.push((item, err));
+                    continue;
+                }
+            };
+            match groups.iter().position(|(group_url, _)| *group_url == url) {
+                Some(position) => groups[position].1.push(item),
+                None => groups.push((url, vec![item]))
+            }
+        }
+        (groups, unroutable)
+    }
+
+    fn leader_url(&self, state: &SolrClusterState, id: Option<&str>) -> Result<Url, SolrError> {
+        let id = match id {
+            Some(id) if state.router == "compositeId" => id,
+            _ => return match state.active_replicas().first() {
+                Some(replica) => state.collection_url(replica),
+                None => Err(SolrError::Unavailable(format!("No active replicas of {}", state.collection)))
+            }
+        };
+        let shard = try!(self.router.shard_for_id(state, id)
+            .ok_or_else(|| SolrError::Unavailable(format!("No active shard for document {}", id))));
+        match shard.leader() {
+            Some(leader) if state.is_active(leader) => leader.core_url(),
+            _ => Err(SolrError::Unavailable(format!("No active leader of shard {}", shard.name)))
+        }
+    }
+}
+
+// Items grouped by the URL of the shard leader they are routed to
+type LeaderGroups<'a, T> = Vec<(Url, Vec<&'a T>)>;
+
+// Errors caused by a node being down, a replica moved or a leader changed
+fn is_stale_state(err: &SolrError) -> bool {
+    match *err {
+        SolrError::Transport { .. } | SolrError::Unavailable(_) => true,
+        SolrError::Http { status, .. } | SolrError::Solr { status, .. } => status == 404 || status == 503 || status == 510,
+        _ => false
+    }
+}
@@ -0,0 +1,166 @@
+use rustc_serialize::json::Json;
+use url::Url;
+use url::percent_encoding::lossy_utf8_percent_decode;
+
+use response::SolrError;
+
+/// Replica of a shard, as listed by CLUSTERSTATUS
+#[derive(Clone, Debug, PartialEq)]
+pub struct SolrReplica {
+    pub name: String,
+    /// Name of the core holding the replica
+    pub core: String,
+    /// Base URL of the node, e.g. `http://127.0.0.1:8983/solr`
+    pub base_url: String,
+    /// Name of the node, as listed in live nodes
+    pub node_name: String,
+    pub state: String,
+    pub leader: bool
+}
+
+impl SolrReplica {
+    /// Returns the URL of the core holding the replica
+    pub fn core_url(&self) -> Result<Url, SolrError> {
+        node_url(&self.base_url, &self.core)
+    }
+}
+
+/// Shard of a collection, with the range of document hashes it holds
+#[derive(Clone, Debug, PartialEq)]
+pub struct SolrShard {
+    pub name: String,
+    /// Inclusive range of hashes, missing for collections using the implicit router
+    pub range: Option<(i32, i32)>,
+    pub state: String,
+    pub replicas: Vec<SolrReplica>
+}
+
+impl SolrShard {
+    /// Returns the leader of the shard
+    pub fn leader(&self) -> Option<&SolrReplica> {
+        self.replicas.iter().find(|replica| replica.leader)
+    }
+
+    /// Tells if a document hash falls into the range of the shard
+    pub fn contains(&self, hash: i32) -> bool {
+        self.range.is_some_and(|(min, max)| min <= hash && hash <= max)
+    }
+}
+
+/// State of a collection in a SolrCloud cluster, read from the Collections API
+#[derive(Clone, Debug, PartialEq)]
+pub struct SolrClusterState {
+    pub collection: String,
+    /// Name of the document router, e.g. `compositeId` or `implicit`
+    pub router: String,
+    pub shards: Vec<SolrShard>,
+    /// Names of nodes currently in the cluster
+    pub live_nodes: Vec<String>
+}
+
+impl SolrClusterState {
+    /// Reads the state of a collection from a CLUSTERSTATUS response
+    pub fn from_json(json: &Json, collection: &str) -> Result<SolrClusterState, SolrError> {
+        let cluster = try!(json.find("cluster").ok_or_else(|| SolrError::parse("CLUSTERSTATUS response has no cluster")));
+        let live_nodes = cluster.find("live_nodes").and_then(|nodes| nodes.as_array())
+            .map_or(Vec::new(), |nodes| nodes.iter().filter_map(|node| node.as_string()).map(|node| node.to_string()).collect());
+        let state = try!(cluster.find_path(&["collections", collection])
+            .ok_or_else(|| SolrError::parse(&format!("Collection {} not found in cluster state", collection))));
+        let router = state.find_path(&["router", "name"]).and_then(|name| name.as_string()).unwrap_or("compositeId");
+        // Clusters using TLS set the urlScheme cluster property to https
+        let scheme = cluster.find_path(&["properties", "urlScheme"]).and_then(|scheme| scheme.as_string()).unwrap_or("http");
+        let mut shards = Vec::new();
+        if let Some(shard_map) = state.find("shards").and_then(|shards| shards.as_object()) {
+            for (name, shard) in shard_map {
+                shards.push(try!(parse_shard(name, shard, scheme)));
+            }
+        }
+        Ok(SolrClusterState { collection: collection.to_string(), router: router.to_string(), shards, live_nodes })
+    }
+
+    /// Returns active replicas on live nodes, of all active shards
+    pub fn active_replicas(&self) -> Vec<&SolrReplica> {
+        self.shards.iter()
+            .filter(|shard| shard.state == "active")
+            .flat_map(|shard| shard.replicas.iter())
+            .filter(|replica| self.is_active(replica))
+            .collect()
+    }
+
+    /// Returns the active shard holding documents with a given hash
+    pub fn shard_for_hash(&self, hash: i32) -> Option<&SolrShard> {
+        self.shards.iter().find(|shard| shard.state == "active" && shard.contains(hash))
+    }
+
+    /// Tells if a replica is active and its node is live
+    pub fn is_active(&self, replica: &SolrReplica) -> bool {
+        replica.state == "active" && self.live_nodes.contains(&replica.node_name)
+    }
+
+    /// Returns the URL of the collection on a node
+    pub fn collection_url(&self, replica: &SolrReplica) -> Result<Url, SolrError> {
+        node_url(&replica.base_url, &self.collection)
+    }
+}
+
+fn node_url(base_url: &str, name: &str) -> Result<Url, SolrError> {
+    Url::parse(&format!("{}/{}/", base_url.trim_end_matches('/'), name)).map_err(|err| SolrError::Parse {
+        message: format!("Invalid URL of replica {} on {}", name, base_url),
+        source: Some(Box::new(err))
+    })
+}
+
+// Node names are written as host:port_context, with slashes in the context percent encoded,
+// e.g. 127.0.0.1:8983_solr for http://127.0.0.1:8983/solr
+fn base_url_of_node(node_name: &str, scheme: &str) -> Option<String> {
+    let separator = node_name.find('_');
+    let host = &node_name[..separator.unwrap_or(node_name.len())];
+    if host.is_empty() {
+        return None;
+    }
+    match separator.map(|separator| lossy_utf8_percent_decode(&node_name.as_bytes()[separator + 1..])) {
+        Some(ref context) if !context.is_empty() => Some(format!("{}://{}/{}", scheme, host, context)),
+        _ => Some(format!("{}://{}", scheme, host))
+    }
+}
+
+fn parse_shard(name: &str, shard: &Json, scheme: &str) -> Result<SolrShard, SolrError> {
+    let range = match shard.find("range").and_then(|range| range.as_string()) {
+        Some(range) => Some(try!(parse_range(range))),
+        None => None
+    };
+    let mut replicas = Vec::new();
+    if let Some(replica_map) = shard.find("replicas").and_then(|replicas| replicas.as_object()) {
+        for (name, replica) in replica_map {
+            let field = |field: &str| replica.find(field).and_then(|value| value.as_string()).unwrap_or("").to_string();
+            let node_name = field("node_name");
+            // Replicas which can't be reached are left out, as if they were down
+            let base_url = match Some(field("base_url")).filter(|url| !url.is_empty()).or_else(|| base_url_of_node(&node_name, scheme)) {
+                Some(base_url) => base_url,
+                None => continue
+            };
+            let replica = SolrReplica {
+                name: name.to_string(),
+                core: field("core"),
+                base_url,
+                node_name,
+                state: field("state"),
+                leader: field("leader") == "true"
+            };
+            if replica.core_url().is_ok() {
+                replicas.push(replica);
+            }
+        }
+    }
+    let state = shard.find("state").and_then(|state| state.as_string()).unwrap_or("active");
+    Ok(SolrShard { name: name.to_string(), range, state: state.to_string(), replicas })
+}
+
+// Ranges are written as hexadecimal two's complement, e.g. 80000000-ffffffff
+fn parse_range(range: &str) -> Result<(i32, i32), SolrError> {
+    let mut bounds = range.splitn(2, '-').map(|bound| u32::from_str_radix(bound, 16).map(|bound| bound as i32));
+    match (bounds.next(), bounds.next()) {
+        (Some(Ok(min)), Some(Ok(max))) => Ok((min, max)),
+        _ => Err(SolrError::parse(&format!("Invalid shard range {}", range)))
+    }
+}
@@ -0,0 +1,130 @@
+/// Represents parameters of the CSV update handler: separator, encapsulator, header line,
+/// field names, skipped fields and lines, splitting of multi-valued fields and value mappings.
+#[derive(Clone, Debug, Default)]
+pub struct SolrCsvOptions {
+    separator: Option<char>,
+    header: Option<bool>,
+    fieldnames: Option<Vec<String>>,
+    skip: Option<Vec<String>>,
+    skip_lines: Option<u32>,
+    encapsulator: Option<char>,
+    split: Option<bool>,
+    field_splits: Vec<(String, char)>,
+    maps: Vec<(Option<String>, String, String)>
+}
+
+impl SolrCsvOptions {
+    /// Creates CSV options with Solr defaults: comma separated values with a header line
+    pub fn new() -> SolrCsvOptions {
+        SolrCsvOptions::default()
+    }
+
+    /// Sets the character separating values (separator)
+    pub fn separator(&self, separator: char) -> SolrCsvOptions {
+        let mut options = self.clone();
+        options.separator = Some(separator);
+        options
+    }
+
+    /// Sets whether the first line contains field names (header)
+    pub fn header(&self, header: bool) -> SolrCsvOptions {
+        let mut options = self.clone();
+        options.header = Some(header);
+        options
+    }
+
+    /// Sets field names for the columns, used instead of or when there is no header (fieldnames)
+    pub fn fieldnames(&self, fieldnames: &[&str]) -> SolrCsvOptions {
+        let mut options = self.clone();
+        options.fieldnames = Some(fieldnames.iter().map(|x| x.to_string()).collect());
+        options
+    }
+
+    /// Sets fields which are not indexed (skip)
+    pub fn skip(&self, fields: &[&str]) -> SolrCsvOptions {
+        let mut options = self.clone();
+        options.skip = Some(fields.iter().map(|x| x.to_string()).collect());
+        options
+    }
+
+    /// Sets the number of lines skipped before the header or data (skipLines)
+    pub fn skip_lines(&self, lines: u32) -> SolrCsvOptions {
+        let mut options = self.clone();
+        options.skip_lines = Some(lines);
+        options
+    }
+
+    /// Sets the character optionally enclosing values, e.g. `"` (encapsulator)
+    pub fn encapsulator(&self, encapsulator: char) -> SolrCsvOptions {
+        let mut options = self.clone();
+        options.encapsulator = Some(encapsulator);
+        options
+    }
+
+    /// Sets whether values are split into multiple values (split)
+    pub fn split(&self, split: bool) -> SolrCsvOptions {
+        let mut options = self.clone();
+        options.split = Some(split);
+        options
+    }
+
+    /// Splits values of a single field into multiple values by a separator
+    /// (f.field.split and f.field.separator)
+    pub fn split_field(&self, field: &str, separator: char) -> SolrCsvOptions {
+        let mut options = self.clone();
+        options.field_splits.push((field.to_string(), separator));
+        options
+    }
+
+    /// Replaces a value with another one in all fields (map=from:to)
+    pub fn add_map(&self, from: &str, to: &str) -> SolrCsvOptions {
+        let mut options = self.clone();
+        options.maps.push((None, from.to_string(), to.to_string()));
+        options
+    }
+
+    /// Replaces a value with another one in a single field (f.field.map=from:to)
+    pub fn add_field_map(&self, field: &str, from: &str, to: &str) -> SolrCsvOptions {
+        let mut options = self.clone();
+        options.maps.push((Some(field.to_string()), from.to_string(), to.to_string()));
+        options
+    }
+
+    /// Converts these options to a vector of pairs, suitable for URL percent encoding
+    pub fn to_pairs(&self) -> Vec<(String, String)> {
+        let mut vec = Vec::new();
+        if let Some(separator) = self.separator {
+            vec.push(("separator".to_string(), separator.to_string()));
+        }
+        if let Some(header) = self.header {
+            vec.push(("header".to_string(), header.to_string()));
+        }
+        if let Some(ref fieldnames) = self.fieldnames {
+            vec.push(("fieldnames".to_string(), fieldnames.join(",")));
+        }
+        if let Some(ref skip) = self.skip {
+            vec.push(("skip".to_string(), skip.join(",")));
+        }
+        if let Some(skip_lines) = self.skip_lines {
+            vec.push(("skipLines".to_string(), skip_lines.to_string()));
+        }
+        if let Some(encapsulator) = self.encapsulator {
+            vec.push(("encapsulator".to_string(), encapsulator.to_string()));
+        }
+        if let Some(split) = self.split {
+            vec.push(("split".to_string(), split.to_string()));
+        }
+        for (field, separator) in &self.field_splits {
+            vec.push((format!("f.{}.split", field), "true".to_string()));
+            vec.push((format!("f.{}.separator", field), separator.to_string()));
+        }
+        for (field, from, to) in &self.maps {
+            let name = match field {
+                Some(f) => format!("f.{}.map", f),
+                None => "map".to_string()
+            };
+            vec.push((name, format!("{}:{}", from, to)));
+        }
+        vec
+    }
+}
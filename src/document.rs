@@ -1,6 +1,10 @@
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::slice::Iter;
 use rustc_serialize::{Encodable, Encoder};
+use xml::{escape, ToXml};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum SolrValue {
     I64(i64),
     U64(u64),
@@ -9,6 +13,44 @@ pub enum SolrValue {
     Boolean(bool),
     Null
 }
+
+impl SolrValue {
+    /// Returns the value as a string slice, if it is a string
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            SolrValue::String(ref v) => Some(v),
+            _ => None
+        }
+    }
+
+    /// Returns the value as i64, if it is an integer that fits into i64
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            SolrValue::I64(v) => Some(v),
+            SolrValue::U64(v) if v <= i64::MAX as u64 => Some(v as i64),
+            _ => None
+        }
+    }
+
+    /// Returns the value as f64, if it is a number
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            SolrValue::I64(v) => Some(v as f64),
+            SolrValue::U64(v) => Some(v as f64),
+            SolrValue::F64(v) => Some(v),
+            _ => None
+        }
+    }
+
+    /// Returns the value as bool, if it is a boolean
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            SolrValue::Boolean(v) => Some(v),
+            _ => None
+        }
+    }
+}
+
 impl Encodable for SolrValue {
     fn encode<E: Encoder>(&self, e: &mut E) -> Result<(), E::Error> {
         match *self {
@@ -17,34 +59,247 @@ impl Encodable for SolrValue {
             SolrValue::F64(v) => v.encode(e),
             SolrValue::String(ref v) => v.encode(e),
             SolrValue::Boolean(v) => v.encode(e),
-            SolrValue::Null => "null".encode(e)
+            SolrValue::Null => e.emit_nil()
+        }
+    }
+}
+/// Atomic update operation applied to a field of an existing document
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum AtomicUpdate {
+    /// Sets or replaces the field value, Null removes the field
+    Set,
+    /// Adds the value to a multi-valued field
+    Add,
+    /// Removes the value from a multi-valued field
+    Remove,
+    /// Removes values matching the regular expression from a multi-valued field
+    RemoveRegex,
+    /// Increments a numeric field by the value
+    Inc
+}
+
+impl AtomicUpdate {
+    /// Name of the operation in Solr update syntax
+    pub fn name(&self) -> &'static str {
+        match *self {
+            AtomicUpdate::Set => "set",
+            AtomicUpdate::Add => "add",
+            AtomicUpdate::Remove => "remove",
+            AtomicUpdate::RemoveRegex => "removeregex",
+            AtomicUpdate::Inc => "inc"
         }
     }
 }
+
 /// SolrDocument field
 #[derive(Debug)]
 pub struct SolrField {
     pub name: String,
-    pub value: SolrValue
+    pub value: SolrValue,
+    /// Index time boost of the field
+    pub boost: Option<f32>,
+    /// Atomic update operation, when the field updates an existing document
+    pub update: Option<AtomicUpdate>
+}
+
+impl Encodable for SolrField {
+    fn encode<E: Encoder>(&self, e: &mut E) -> Result<(), E::Error> {
+        match (self.update, self.boost) {
+            (Some(update), _) => e.emit_struct("SolrField", 1, |e| {
+                e.emit_struct_field(update.name(), 0, |e| self.value.encode(e))
+            }),
+            (None, Some(boost)) => e.emit_struct("SolrField", 2, |e| {
+                try!(e.emit_struct_field("boost", 0, |e| boost.encode(e)));
+                e.emit_struct_field("value", 1, |e| self.value.encode(e))
+            }),
+            (None, None) => self.value.encode(e)
+        }
+    }
+}
+
+/// Fields of a SolrDocument in insertion order, readable as a slice.
+/// They are modified through SolrDocument, which keeps them indexed by name.
+#[derive(Debug)]
+pub struct SolrFields {
+    // Document fields, in insertion order
+    list: Vec<SolrField>,
+    // Positions of each field name in `list`, for constant time lookups
+    index: HashMap<String, Vec<usize>>
+}
+
+impl SolrFields {
+    fn push(&mut self, field: SolrField) {
+        self.index.entry(field.name.clone()).or_default().push(self.list.len());
+        self.list.push(field);
+    }
+
+    fn reindex(&mut self) {
+        self.index.clear();
+        for (i, field) in self.list.iter().enumerate() {
+            self.index.entry(field.name.clone()).or_default().push(i);
+        }
+    }
+}
+
+impl Deref for SolrFields {
+    type Target = [SolrField];
+
+    fn deref(&self) -> &[SolrField] {
+        &self.list
+    }
+}
+
+impl<'a> IntoIterator for &'a SolrFields {
+    type Item = &'a SolrField;
+    type IntoIter = Iter<'a, SolrField>;
+
+    fn into_iter(self) -> Iter<'a, SolrField> {
+        self.list.iter()
+    }
 }
 
 /// SolrDocument to be used to either index or query.
+/// Multi-valued fields are stored as several fields with the same name.
 #[derive(Debug)]
 pub struct SolrDocument {
     /// Collection of document fields
-    pub fields: Vec<SolrField>
+    pub fields: SolrFields,
+    // Index time boost of the whole document
+    boost: Option<f32>
 }
 
 impl SolrDocument {
     /// Creates new empty SolrDocument
     pub fn new() -> SolrDocument {
         let fields: Vec<SolrField> = Vec::with_capacity(10);
-        SolrDocument{fields: fields}
+        SolrDocument{fields: SolrFields{list: fields, index: HashMap::new()}, boost: None}
     }
 
     /// Adds a field to the document
     pub fn add_field(&mut self, name: &str, value: &str) {
-        self.fields.push(SolrField{name: name.to_string(), value: SolrValue::String(value.to_string())});
+        self.add_value(name, SolrValue::String(value.to_string()));
+    }
+
+    /// Adds a field with a typed value to the document.
+    /// Adding a field that already exists makes it multi-valued.
+    pub fn add_value(&mut self, name: &str, value: SolrValue) {
+        self.fields.push(SolrField{name: name.to_string(), value, boost: None, update: None});
+    }
+
+    /// Adds a field with an index time boost to the document
+    pub fn add_field_with_boost(&mut self, name: &str, value: SolrValue, boost: f32) {
+        self.fields.push(SolrField{name: name.to_string(), value, boost: Some(boost), update: None});
+    }
+
+    /// Adds an atomic update of a field.
+    /// A document with atomic updates modifies the existing document with the same unique key.
+    pub fn add_update(&mut self, name: &str, update: AtomicUpdate, value: SolrValue) {
+        self.fields.push(SolrField{name: name.to_string(), value, boost: None, update: Some(update)});
+    }
+
+    /// Sets index time boost of the whole document
+    pub fn set_boost(&mut self, boost: f32) {
+        self.boost = Some(boost);
+    }
+
+    /// Returns index time boost of the whole document
+    pub fn boost(&self) -> Option<f32> {
+        self.boost
+    }
+
+    /// Replaces all values of a field with a single value.
+    /// The field keeps the position of its first occurrence, or is appended if absent.
+    pub fn set_field(&mut self, name: &str, value: &str) {
+        self.set_value(name, SolrValue::String(value.to_string()));
+    }
+
+    /// Replaces all values of a field with a single typed value.
+    pub fn set_value(&mut self, name: &str, value: SolrValue) {
+        let positions = match self.fields.index.get(name) {
+            Some(positions) => positions.clone(),
+            None => return self.add_value(name, value)
+        };
+        self.fields.list[positions[0]] = SolrField{name: name.to_string(), value, boost: None, update: None};
+        if positions.len() > 1 {
+            for &i in positions[1..].iter().rev() {
+                self.fields.list.remove(i);
+            }
+            self.fields.reindex();
+        }
+    }
+
+    /// Removes a field with all its values, returning the removed values
+    pub fn remove_field(&mut self, name: &str) -> Vec<SolrValue> {
+        if !self.fields.index.contains_key(name) {
+            return Vec::new();
+        }
+        let fields = ::std::mem::take(&mut self.fields.list);
+        let (removed, kept): (Vec<SolrField>, Vec<SolrField>) = fields.into_iter().partition(|f| f.name == name);
+        self.fields.list = kept;
+        self.fields.reindex();
+        removed.into_iter().map(|f| f.value).collect()
+    }
+
+    /// Returns true if the document has at least one value for the field
+    pub fn contains_field(&self, name: &str) -> bool {
+        self.fields.index.contains_key(name)
+    }
+
+    /// Returns the first value of a field
+    pub fn get(&self, name: &str) -> Option<&SolrValue> {
+        self.fields.index.get(name).map(|positions| &self.fields.list[positions[0]].value)
+    }
+
+    /// Returns all values of a (multi-valued) field, in insertion order
+    pub fn get_all(&self, name: &str) -> Vec<&SolrValue> {
+        match self.fields.index.get(name) {
+            Some(positions) => positions.iter().map(|&i| &self.fields.list[i].value).collect(),
+            None => Vec::new()
+        }
+    }
+
+    /// Returns the first value of a field if it is a string
+    pub fn get_str(&self, name: &str) -> Option<&str> {
+        self.get(name).and_then(|v| v.as_str())
+    }
+
+    /// Returns the first value of a field if it is an integer
+    pub fn get_i64(&self, name: &str) -> Option<i64> {
+        self.get(name).and_then(|v| v.as_i64())
+    }
+
+    /// Returns the first value of a field if it is a number
+    pub fn get_f64(&self, name: &str) -> Option<f64> {
+        self.get(name).and_then(|v| v.as_f64())
+    }
+
+    /// Returns the first value of a field if it is a boolean
+    pub fn get_bool(&self, name: &str) -> Option<bool> {
+        self.get(name).and_then(|v| v.as_bool())
+    }
+
+    /// Iterates over fields in insertion order
+    pub fn iter(&self) -> Iter<'_, SolrField> {
+        self.fields.iter()
+    }
+
+    /// Number of field values in the document
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// Returns true if the document has no fields
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+}
+
+impl<'a> IntoIterator for &'a SolrDocument {
+    type Item = &'a SolrField;
+    type IntoIter = Iter<'a, SolrField>;
+
+    fn into_iter(self) -> Iter<'a, SolrField> {
+        self.fields.iter()
     }
 }
 
@@ -53,10 +308,44 @@ impl Encodable for SolrDocument {
         let mut i = 0usize;
         s.emit_struct("SolrDocument", self.fields.len(), |e| {
             for field in self.fields.iter() {
-                try!(e.emit_struct_field(&field.name, i, |e| field.value.encode(e)));
+                try!(e.emit_struct_field(&field.name, i, |e| field.encode(e)));
                 i = i + 1;
             }
             Ok(())
         })
     }
 }
+
+impl ToXml for SolrDocument {
+    fn to_xml(&self) -> String {
+        let mut xml = match self.boost {
+            Some(boost) => format!("<doc boost=\"{}\">", boost),
+            None => String::from("<doc>")
+        };
+        for field in self.fields.iter() {
+            xml.push_str(&format!("<field name=\"{}\"", escape(&field.name)));
+            if let Some(boost) = field.boost {
+                xml.push_str(&format!(" boost=\"{}\"", boost));
+            }
+            if let Some(update) = field.update {
+                xml.push_str(&format!(" update=\"{}\"", update.name()));
+            }
+            match field.value {
+                SolrValue::Null => xml.push_str(" null=\"true\"/>"),
+                ref value => {
+                    let text = match *value {
+                        SolrValue::I64(v) => v.to_string(),
+                        SolrValue::U64(v) => v.to_string(),
+                        SolrValue::F64(v) => v.to_string(),
+                        SolrValue::String(ref v) => escape(v),
+                        SolrValue::Boolean(v) => v.to_string(),
+                        SolrValue::Null => unreachable!()
+                    };
+                    xml.push_str(&format!(">{}</field>", text));
+                }
+            }
+        }
+        xml.push_str("</doc>");
+        xml
+    }
+}
@@ -1,58 +1,389 @@
-use url::Url;
+use std::time::Duration;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
 use hyper::Client;
-use hyper::header::{ContentType};
-use hyper::status::StatusCode;
+use hyper::client::{Body, ProxyConfig};
+use hyper::client::pool::{Config, Pool, PooledStream};
 use hyper::error::Error;
-use std::io::Read;
-
-
-pub struct HttpResponse {
-    pub status: StatusCode,
-    pub body: String
-}
-
-pub fn get(url: &Url) -> Result<HttpResponse, Error> {
-    let mut client = Client::new();
-    let result_response = client.get(&url.to_string()).send();
-    //TODO: use try! macro here
-    match result_response {
-        Ok(mut res) => {
-            let mut body = String::new();
-            let result = res.read_to_string(&mut body);
-            match result {
-                Ok(_) => {
-                    Ok(HttpResponse{status: res.status, body: body})
-                },
-                //TODO: review why we use Irror::Io here
-                Err(err) => {
-                    Err(Error::Io(err))
-                }
+use hyper::header::{ContentEncoding, ContentLength, Headers};
+use flate2::Compression;
+use flate2::read::{GzDecoder, ZlibDecoder};
+use flate2::write::GzEncoder;
+use hyper::net::{HttpStream, NetworkConnector, NetworkStream, SslClient};
+use openssl::ssl::{SslConnector, SslStream};
+
+use proxy::SolrProxyConfig;
+use tls::{self, SolrTlsConfig};
+use transport::{self, HttpBody, HttpMethod, HttpRequest, HttpResponse, Transport};
+
+/// Settings of HyperTransport
+#[derive(Clone, Debug)]
+pub struct HyperTransportConfig {
+    /// Maximum number of idle connections kept alive per host
+    pub max_idle_connections: usize,
+    /// How long an idle connection may be reused, until the server closes it if None
+    pub idle_timeout: Option<Duration>,
+    /// Maximum time to establish a connection
+    pub connect_timeout: Option<Duration>,
+    /// Maximum time to wait for response data, unless overridden by the request
+    pub read_timeout: Option<Duration>,
+    /// Maximum time to wait while sending request data, unless overridden by the request
+    pub write_timeout: Option<Duration>,
+    /// TLS settings for https URLs
+    pub tls: SolrTlsConfig,
+    /// HTTP proxy to send requests through, directly to Solr if None
+    pub proxy: Option<SolrProxyConfig>,
+    /// Whether gzip and deflate compressed responses are accepted
+    pub response_compression: bool,
+    /// Minimum size of request bodies compressed with gzip, never compressed if None
+    pub request_compression: Option<usize>
+}
+
+impl Default for HyperTransportConfig {
+    fn default() -> HyperTransportConfig {
+        HyperTransportConfig {
+            max_idle_connections: 5,
+            idle_timeout: None,
+            connect_timeout: None,
+            read_timeout: None,
+            write_timeout: None,
+            tls: SolrTlsConfig::default(),
+            proxy: None,
+            response_compression: true,
+            request_compression: None
+        }
+    }
+}
+
+/// Default transport, sending requests with hyper over a keep-alive connection pool.
+/// The pool is shared between threads and by requests with different timeouts.
+pub struct HyperTransport {
+    pool: Arc<Pool<SolrConnector>>,
+    read_timeout: Option<Duration>,
+    write_timeout: Option<Duration>,
+    proxy: Option<SolrProxyConfig>,
+    response_compression: bool,
+    request_compression: Option<usize>
+}
+
+impl HyperTransport {
+    /// Creates a transport with given settings.
+    /// Invalid TLS settings, e.g. a missing CA file, are reported by https requests.
+    pub fn new(config: HyperTransportConfig) -> HyperTransport {
+        let tls = tls::connector(&config.tls).map_err(|err| format!("Invalid TLS configuration: {}", err));
+        let connector = SolrConnector { timeout: config.connect_timeout, tls, proxy: config.proxy.clone() };
+        let mut pool = Pool::with_connector(Config { max_idle: config.max_idle_connections }, connector);
+        pool.set_idle_timeout(config.idle_timeout);
+        HyperTransport {
+            pool: Arc::new(pool),
+            read_timeout: config.read_timeout,
+            write_timeout: config.write_timeout,
+            proxy: config.proxy,
+            response_compression: config.response_compression,
+            request_compression: config.request_compression
+        }
+    }
+
+    // hyper applies timeouts per Client, so a lightweight Client is created
+    // for each request on top of the shared pool
+    fn client(&self, timeout: Option<Duration>, proxy: Option<&SolrProxyConfig>) -> Client {
+        let mut client = match proxy {
+            Some(proxy) => {
+                let mut config = ProxyConfig::new("http", proxy.host.clone(), proxy.port, ProxyPool(self.pool.clone()), NoTls);
+                config.set_pool_config(None);
+                Client::with_proxy_config(config)
+            },
+            None => Client::with_connector(SharedPool(self.pool.clone()))
+        };
+        client.set_read_timeout(timeout.or(self.read_timeout));
+        client.set_write_timeout(timeout.or(self.write_timeout));
+        client
+    }
+}
+
+impl Transport for HyperTransport {
+    fn execute(&self, request: HttpRequest) -> io::Result<HttpResponse> {
+        let url = request.url.to_string();
+        // https requests are tunneled by SolrConnector, http ones are forwarded by the proxy
+        let proxy = self.proxy.as_ref().filter(|proxy| {
+            request.url.scheme == "http" && !proxy.is_bypassed(&request.url.serialize_host().unwrap_or_default())
+        });
+        let mut headers = Headers::new();
+        for (name, value) in request.headers {
+            headers.set_raw(name, vec![value.into_bytes()]);
+        }
+        if let Some(authorization) = proxy.and_then(|proxy| proxy.proxy_authorization()) {
+            headers.set_raw("Proxy-Authorization", vec![authorization.into_bytes()]);
+        }
+        if self.response_compression && headers.get_raw("Accept-Encoding").is_none() {
+            headers.set_raw("Accept-Encoding", vec![b"gzip, deflate".to_vec()]);
+        }
+        // Solr reads form parameters before any decompression, so forms are sent as they are
+        let is_form = headers.get_raw("Content-Type").is_some_and(|value| value[0] == b"application/x-www-form-urlencoded");
+        let compressed = match (&request.body, self.request_compression) {
+            (HttpBody::Text(text), Some(min_size)) if text.len() >= min_size && !is_form => {
+                headers.set_raw("Content-Encoding", vec![b"gzip".to_vec()]);
+                Some(try!(gzip(text)))
+            },
+            _ => None
+        };
+        let client = self.client(request.timeout, proxy);
+        let builder = match request.method {
+            HttpMethod::Get => client.get(&url),
+            HttpMethod::Post => client.post(&url)
+        }.headers(headers);
+        let result = match (request.body, compressed) {
+            (_, Some(ref compressed)) => builder.body(&compressed[..]).send(),
+            (HttpBody::Empty, None) => builder.send(),
+            (HttpBody::Text(text), None) => builder.body(text).send(),
+            (HttpBody::Stream(reader), None) => builder.body(Body::ChunkedBody(reader)).send()
+        };
+        let response = try!(result.map_err(to_io_error));
+        let status = response.status.to_u16();
+        let encoding = response.headers.get_raw("Content-Encoding")
+            .map_or(String::new(), |values| String::from_utf8_lossy(&values[0]).trim().to_lowercase());
+        let decoded = is_decoded(&encoding);
+        // decoded bodies are returned without the headers describing the encoded ones
+        let headers = response.headers.iter()
+            .filter(|header| !decoded || !(header.is::<ContentEncoding>() || header.is::<ContentLength>()))
+            .map(|header| (header.name().to_string(), header.value_string()))
+            .collect();
+        Ok(HttpResponse { status, headers, body: decode(&encoding, response) })
+    }
+}
+
+/// Tells if responses with a Content-Encoding, in lower case, are decoded
+pub fn is_decoded(encoding: &str) -> bool {
+    ["gzip", "x-gzip", "deflate"].contains(&encoding)
+}
+
+/// Wraps a response body in a decoder of its Content-Encoding, in lower case
+pub fn decode<R: Read + 'static>(encoding: &str, body: R) -> Box<dyn Read> {
+    match encoding {
+        "gzip" | "x-gzip" => Box::new(GzDecoder::new(body)),
+        "deflate" => Box::new(ZlibDecoder::new(body)),
+        _ => Box::new(body)
+    }
+}
+
+pub fn gzip(text: &str) -> io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    try!(encoder.write_all(text.as_bytes()));
+    encoder.finish()
+}
+
+fn to_io_error(err: Error) -> io::Error {
+    match err {
+        Error::Io(err) => err,
+        err => io::Error::other(err)
+    }
+}
+
+// Marks timeouts as connect timeouts, so they aren't mistaken for read timeouts
+fn as_connect_timeout(err: io::Error) -> io::Error {
+    if transport::is_timeout(&err) { transport::connect_timeout_error(err) } else { err }
+}
+
+// Connects TCP streams, giving up after a timeout, and wraps them in TLS for https URLs.
+// https connections go through a CONNECT tunnel when a proxy is set.
+struct SolrConnector {
+    timeout: Option<Duration>,
+    tls: Result<SslConnector, String>,
+    proxy: Option<SolrProxyConfig>
+}
+
+impl SolrConnector {
+    fn connect_tcp(&self, host: &str, port: u16) -> io::Result<TcpStream> {
+        let timeout = match self.timeout {
+            Some(timeout) => timeout,
+            None => return TcpStream::connect((host, port)).map_err(as_connect_timeout)
+        };
+        let mut last_error = io::Error::new(io::ErrorKind::InvalidInput, "Host not resolved");
+        for addr in try!((host, port).to_socket_addrs()) {
+            match TcpStream::connect_timeout(&addr, timeout) {
+                Ok(stream) => return Ok(stream),
+                Err(err) => last_error = as_connect_timeout(err)
             }
-        },
-        Err(err) => Err(err)
-    }
-}
-
-
-pub fn post_json(url: &Url, body: &str) -> Result<HttpResponse, Error> {
-    let mut client = Client::new();
-    let result_response = client.post(&url.to_string())
-        .header(ContentType::json())
-        .body(body)
-        .send();
-    match result_response {
-        Ok(mut res) => {
-            let mut body = String::new();
-            let result = res.read_to_string(&mut body);
-            match result {
-                Ok(_) => {
-                    Ok(HttpResponse{status: res.status, body: body})
-                },
-                Err(err) => {
-                    Err(Error::Io(err))
-                }
+        }
+        Err(last_error)
+    }
+
+    fn connect_tunnel(&self, host: &str, port: u16) -> io::Result<TcpStream> {
+        let proxy = match self.proxy {
+            Some(ref proxy) if !proxy.is_bypassed(host) => proxy,
+            _ => return self.connect_tcp(host, port)
+        };
+        let mut stream = try!(self.connect_tcp(&proxy.host, proxy.port));
+        let mut connect = format!("CONNECT {0}:{1} HTTP/1.1\r\nHost: {0}:{1}\r\n", host, port);
+        if let Some(authorization) = proxy.proxy_authorization() {
+            connect.push_str(&format!("Proxy-Authorization: {}\r\n", authorization));
+        }
+        connect.push_str("\r\n");
+        try!(stream.write_all(connect.as_bytes()));
+        try!(stream.set_read_timeout(self.timeout));
+        // read the response byte by byte, not to consume the start of the TLS handshake
+        let mut response = Vec::new();
+        let mut byte = [0; 1];
+        while !response.ends_with(b"\r\n\r\n") {
+            if try!(stream.read(&mut byte).map_err(as_connect_timeout)) == 0 || response.len() > 8192 {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "Invalid response of the proxy to CONNECT"));
             }
-        },
-        Err(err) => Err(err)
+            response.push(byte[0]);
+        }
+        try!(stream.set_read_timeout(None));
+        let response = String::from_utf8_lossy(&response);
+        let status_line = response.lines().next().unwrap_or("");
+        match status_line.split_whitespace().nth(1) {
+            Some(status) if status.starts_with('2') => Ok(stream),
+            _ => Err(io::Error::other(format!("Proxy refused to connect to {}:{}: {}", host, port, status_line)))
+        }
+    }
+}
+
+impl NetworkConnector for SolrConnector {
+    type Stream = SolrStream;
+
+    fn connect(&self, host: &str, port: u16, scheme: &str) -> ::hyper::Result<SolrStream> {
+        match scheme {
+            "http" => Ok(SolrStream::Plain(HttpStream(try!(self.connect_tcp(host, port))))),
+            "https" => {
+                let connector = try!(self.tls.as_ref().map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err.clone())));
+                let stream = try!(self.connect_tunnel(host, port));
+                match connector.connect(host, stream) {
+                    Ok(stream) => Ok(SolrStream::Tls(stream)),
+                    Err(err) => Err(Error::Io(io::Error::other(format!("TLS handshake failed: {}", err))))
+                }
+            },
+            _ => Err(Error::Io(io::Error::new(io::ErrorKind::InvalidInput, "Invalid scheme for Http")))
+        }
+    }
+}
+
+// A plain or TLS connection
+enum SolrStream {
+    Plain(HttpStream),
+    Tls(SslStream<TcpStream>)
+}
+
+impl SolrStream {
+    fn tcp(&self) -> &TcpStream {
+        match *self {
+            SolrStream::Plain(ref stream) => &stream.0,
+            SolrStream::Tls(ref stream) => stream.get_ref()
+        }
+    }
+}
+
+impl Read for SolrStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match *self {
+            SolrStream::Plain(ref mut stream) => stream.read(buf),
+            SolrStream::Tls(ref mut stream) => stream.read(buf)
+        }
     }
-} 
\ No newline at end of file
+}
+
+impl Write for SolrStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match *self {
+            SolrStream::Plain(ref mut stream) => stream.write(buf),
+            SolrStream::Tls(ref mut stream) => stream.write(buf)
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match *self {
+            SolrStream::Plain(ref mut stream) => stream.flush(),
+            SolrStream::Tls(ref mut stream) => stream.flush()
+        }
+    }
+}
+
+impl NetworkStream for SolrStream {
+    fn peer_addr(&mut self) -> io::Result<SocketAddr> {
+        self.tcp().peer_addr()
+    }
+
+    fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.tcp().set_read_timeout(dur)
+    }
+
+    fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.tcp().set_write_timeout(dur)
+    }
+
+    fn close(&mut self, how: Shutdown) -> io::Result<()> {
+        self.tcp().shutdown(how)
+    }
+}
+
+struct SharedPool(Arc<Pool<SolrConnector>>);
+
+impl NetworkConnector for SharedPool {
+    type Stream = PooledStream<SolrStream>;
+
+    fn connect(&self, host: &str, port: u16, scheme: &str) -> ::hyper::Result<PooledStream<SolrStream>> {
+        self.0.connect(host, port, scheme)
+    }
+}
+
+// hyper forwards http requests to a proxy only through its own proxy connector,
+// which requires cloneable streams, so pooled connections are shared behind a lock
+struct ProxyPool(Arc<Pool<SolrConnector>>);
+
+impl NetworkConnector for ProxyPool {
+    type Stream = ProxyStream;
+
+    fn connect(&self, host: &str, port: u16, scheme: &str) -> ::hyper::Result<ProxyStream> {
+        self.0.connect(host, port, scheme).map(|stream| ProxyStream(Arc::new(Mutex::new(stream))))
+    }
+}
+
+#[derive(Clone)]
+struct ProxyStream(Arc<Mutex<PooledStream<SolrStream>>>);
+
+impl Read for ProxyStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().read(buf)
+    }
+}
+
+impl Write for ProxyStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.lock().unwrap().flush()
+    }
+}
+
+impl NetworkStream for ProxyStream {
+    fn peer_addr(&mut self) -> io::Result<SocketAddr> {
+        self.0.lock().unwrap().peer_addr()
+    }
+
+    fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.lock().unwrap().set_read_timeout(dur)
+    }
+
+    fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.lock().unwrap().set_write_timeout(dur)
+    }
+
+    fn close(&mut self, how: Shutdown) -> io::Result<()> {
+        self.0.lock().unwrap().close(how)
+    }
+}
+
+// https requests are never sent through hyper's proxy connector
+struct NoTls;
+
+impl SslClient<ProxyStream> for NoTls {
+    type Stream = ProxyStream;
+
+    fn wrap_client(&self, _: ProxyStream, _: &str) -> ::hyper::Result<ProxyStream> {
+        Err(Error::Io(io::Error::new(io::ErrorKind::InvalidInput, "https is tunneled by SolrConnector")))
+    }
+}
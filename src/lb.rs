@@ -0,0 +1,118 @@
+use std::sync::{Arc, Mutex, Weak};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+use url::Url;
+
+use client::SolrClient;
+use query::SolrQuery;
+use response::{SolrError, SolrPingResult, SolrQueryResult};
+
+// Clients of all nodes, and which of them are considered dead
+struct Nodes {
+    clients: Vec<SolrClient>,
+    dead: Mutex<Vec<bool>>,
+    next: AtomicUsize
+}
+
+/// Client balancing requests across several Solr nodes serving the same data,
+/// like replicas of a standalone core.
+/// Queries are sent to live nodes in turn. A node which can't be connected to is marked dead
+/// and the request is sent to the next one. Dead nodes are pinged periodically in the background
+/// and come back into rotation once they answer.
+/// Clones share the nodes and their state.
+#[derive(Clone)]
+pub struct SolrLbClient {
+    nodes: Arc<Nodes>
+}
+
+impl SolrLbClient {
+    /// Creates a client for nodes with given base URLs, re-checking dead nodes every minute
+    pub fn new(urls: &[Url]) -> SolrLbClient {
+        SolrLbClient::with_clients(urls.iter().map(SolrClient::new).collect(), Duration::from_secs(60))
+    }
+
+    /// Creates a client balancing requests across configured clients,
+    /// re-checking dead nodes every `recheck_interval`
+    pub fn with_clients(clients: Vec<SolrClient>, recheck_interval: Duration) -> SolrLbClient {
+        let dead = vec![false; clients.len()];
+        let nodes = Arc::new(Nodes { clients, dead: Mutex::new(dead), next: AtomicUsize::new(0) });
+        let weak = Arc::downgrade(&nodes);
+        thread::spawn(move || recheck_dead_nodes(weak, recheck_interval));
+        SolrLbClient { nodes }
+    }
+
+    /// Returns base URLs of nodes considered alive
+    pub fn alive_nodes(&self) -> Vec<Url> {
+        let dead = self.nodes.dead.lock().unwrap();
+        self.nodes.clients.iter().zip(dead.iter())
+            .filter(|&(_, dead)| !dead)
+            .map(|(client, _)| client.base_url.clone())
+            .collect()
+    }
+
+    /// Performs Solr query on the next live node
+    pub fn query(&self, query: &SolrQuery) -> SolrQueryResult {
+        self.request(|client| client.query(query))
+    }
+
+    /// Pings the next live node
+    pub fn ping(&self) -> SolrPingResult {
+        self.request(|client| client.ping())
+    }
+
+    /// Sends any request with the client of the next live node, failing over to other nodes.
+    /// Dead nodes are tried last, when no live node could be reached.
+    /// As an update may be sent more than once, only idempotent updates should be sent this way.
+    pub fn request<T, F>(&self, request: F) -> Result<T, SolrError> where F: Fn(&SolrClient) -> Result<T, SolrError> {
+        let count = self.nodes.clients.len();
+        let start = self.nodes.next.fetch_add(1, Ordering::Relaxed);
+        let dead = self.nodes.dead.lock().unwrap().clone();
+        let order: Vec<usize> = (0..count).map(|i| (start + i) % count).collect();
+        let live = order.iter().filter(|&&node| !dead[node]);
+        let zombies = order.iter().filter(|&&node| dead[node]);
+        let mut last_error = SolrError::Unavailable("No Solr nodes configured".to_string());
+        for &node in live.chain(zombies) {
+            match request(&self.nodes.clients[node]) {
+                Err(err) => if is_node_failure(&err) {
+                    self.nodes.dead.lock().unwrap()[node] = true;
+                    last_error = err;
+                } else {
+                    return Err(err);
+                },
+                result => {
+                    self.nodes.dead.lock().unwrap()[node] = false;
+                    return result;
+                }
+            }
+        }
+        Err(last_error)
+    }
+}
+
+// A node which can't be reached, doesn't accept connections in time or is shutting down.
+// Read timeouts aren't node failures, as a slow query would time out on every node.
+fn is_node_failure(err: &SolrError) -> bool {
+    match *err {
+        SolrError::Transport { .. } | SolrError::Http { status: 503, .. } => true,
+        SolrError::Timeout { connect, .. } => connect,
+        _ => false
+    }
+}
+
+// Pings dead nodes until the client is dropped
+fn recheck_dead_nodes(nodes: Weak<Nodes>, interval: Duration) {
+    loop {
+        thread::sleep(interval);
+        let nodes = match nodes.upgrade() {
+            Some(nodes) => nodes,
+            None => return
+        };
+        let dead = nodes.dead.lock().unwrap().clone();
+        for (node, _) in dead.iter().enumerate().filter(|&(_, dead)| *dead) {
+            if nodes.clients[node].ping().is_ok() {
+                nodes.dead.lock().unwrap()[node] = false;
+            }
+        }
+    }
+}
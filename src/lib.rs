@@ -17,6 +17,210 @@ To use it in your project, add this to your `Cargo.toml` file:
 heliotrope = "~0.0.3"
 ```
 
+## Configuring the client
+
+`SolrClient` keeps connections alive in a pool, which is shared by its clones and between threads.
+Use `SolrClient::builder` to configure it.
+
+```ignore
+use std::time::Duration;
+
+let solr = SolrClient::builder(&url)
+    .max_idle_connections(20)
+    .idle_timeout(Duration::from_secs(30))
+    .build();
+```
+
+### Timeouts
+
+There are no timeouts by default. Connect, read and write timeouts can be set on the builder,
+and read and write timeouts overridden per request. Failures caused by them are reported as
+`SolrError::Timeout`, with `connect` set when the connection wasn't established in time.
+`time_allowed` limits the search time on the Solr side instead.
+
+```ignore
+let solr = SolrClient::builder(&url)
+    .connect_timeout(Duration::from_secs(1))
+    .read_timeout(Duration::from_secs(5))
+    .build();
+let query = SolrQuery::new("*:*").time_allowed(2000).timeout(Duration::from_secs(30));
+match solr.query(&query) {
+    Ok(response) => if response.partial_results { println!("Results may be incomplete") },
+    Err(SolrError::Timeout { .. }) => println!("Solr didn't respond in time"),
+    Err(err) => println!("Error: {}", err)
+}
+```
+
+### Retrying failed requests
+
+With a retry policy, queries, pings and real-time gets failing with a 502, 503 or 504 status
+or a dropped connection are retried with exponential backoff. Updates are retried only when allowed,
+since a retried update may be applied twice. `SolrError::attempts` tells how many times
+a failed request was sent.
+
+```ignore
+let solr = SolrClient::builder(&url)
+    .retry_policy(SolrRetryPolicy::new().max_attempts(5).initial_backoff(Duration::from_millis(200)))
+    .build();
+```
+
+### Authentication and headers
+
+Credentials for Solr's BasicAuth plugin and default headers are sent with every request.
+Queries and update options can add headers replacing default ones with the same name.
+`Debug` output of the client shows neither the password nor header values.
+
+```ignore
+let solr = SolrClient::builder(&url)
+    .basic_auth("solr", "SolrRocks")
+    .default_header("X-Api-Key", &api_key)
+    .build();
+solr.query(&SolrQuery::new("*:*").add_header("X-Request-Id", "42"));
+```
+
+### TLS
+
+https base URLs are served over OpenSSL. Server certificates are verified against the system's
+trusted CAs, unless a CA file is given. A client certificate can be presented for mutual TLS.
+
+```ignore
+let tls = SolrTlsConfig::new()
+    .ca_file("/etc/solr/ca.pem")
+    .client_certificate("/etc/solr/client.pem", "/etc/solr/client.key");
+let solr = SolrClient::builder(&Url::parse("https://solr.internal:8983/solr/test/").unwrap())
+    .tls(tls)
+    .build();
+```
+
+### Proxy
+
+Requests can be sent through an HTTP proxy, authenticating with credentials from its URL or
+set separately. Hosts in the no-proxy list, and their subdomains, are reached directly.
+
+```ignore
+let proxy = SolrProxyConfig::new(&Url::parse("http://proxy.internal:3128").unwrap())
+    .credentials("agent", "secret")
+    .set_no_proxy(&["localhost", ".cluster.local"]);
+let solr = SolrClient::builder(&url).proxy(proxy).build();
+```
+
+### Compression
+
+Responses are requested with gzip or deflate compression and decompressed transparently.
+Update bodies, e.g. of `add_many`, can be compressed with gzip above a given size.
+
+```ignore
+let solr = SolrClient::builder(&url).request_compression(64 * 1024).build();
+```
+
+### Multiple cores and collections
+
+A client of the Solr root URL hands out clients of its cores or collections,
+sharing its transport and configuration.
+
+```ignore
+let solr = SolrClient::builder(&Url::parse("http://localhost:8983/solr/").unwrap())
+    .basic_auth("solr", "SolrRocks")
+    .build();
+let products = solr.collection("products");
+let orders = solr.collection("orders");
+products.query(&SolrQuery::new("*:*"));
+```
+
+### Testing without Solr
+
+Requests are sent through a `Transport`. `RecordingTransport` records them and answers with
+queued responses, so code using `SolrClient` can be tested without a running Solr.
+
+```ignore
+let transport = RecordingTransport::new();
+transport.push_response(200, r#"{"responseHeader":{"status":0,"QTime":1}}"#);
+let solr = SolrClient::builder(&url).transport(transport.clone()).build();
+solr.commit();
+assert_eq!(transport.requests()[0].url, "http://localhost:8983/solr/test/update?wt=json&commit=true");
+```
+
+## Asynchronous client
+
+`SolrAsyncClient` exposes the same operations returning futures, which run on a tokio reactor
+without blocking it. Requests are built and responses parsed by the same code as `SolrClient`'s,
+and sent by `HyperAsyncTransport` over non-blocking hyper connections. It accepts compressed
+responses, but sends only http requests: https URLs fail with an `InvalidInput` error, so implement
+`AsyncTransport` for them.
+
+```ignore
+use futures::Future;
+use tokio_core::reactor::Core;
+
+let mut core = Core::new().unwrap();
+let solr = SolrAsyncClient::new(&url, &core.handle());
+let total = solr.add(&document)
+    .and_then(|_| solr.commit())
+    .and_then(|_| solr.query(&SolrQuery::new("*:*")))
+    .map(|response| response.total);
+core.run(total);
+```
+
+A `SolrClientBuilder` configures the async client too, including the transport's timeouts and
+compression. A builder with a proxy is rejected, as the transport can't send requests through it:
+
+```ignore
+let builder = SolrClient::builder(&url).basic_auth("solr", "secret").read_timeout(Duration::from_secs(5));
+let solr = SolrAsyncClient::from_builder(&builder, &core.handle()).unwrap();
+```
+
+Alternatively, credentials, default headers and the retry policy are taken from a `SolrClient`
+and the transport is configured separately:
+
+```ignore
+let client = SolrClient::builder(&url).basic_auth("solr", "secret").retry_policy(SolrRetryPolicy::new()).build();
+let transport = HyperAsyncTransport::new(&core.handle()).timeout(Duration::from_secs(5));
+let solr = SolrAsyncClient::with_transport(client, transport);
+```
+
+## Load balancing
+
+`SolrLbClient` spreads queries across nodes serving the same data, e.g. replicas of
+a standalone core. Nodes which can't be reached are skipped until they answer a ping again.
+
+```ignore
+let urls = [Url::parse("http://solr1:8983/solr/test/").unwrap(), Url::parse("http://solr2:8983/solr/test/").unwrap()];
+let solr = SolrLbClient::new(&urls);
+solr.query(&SolrQuery::new("*:*"));
+solr.request(|client| client.commit());
+```
+
+## SolrCloud
+
+`SolrCloudClient` reads the state of a collection from the Collections API of any node.
+Queries go to active replicas, documents to leaders of the shards they are routed to
+by the compositeId router. The state is refreshed when nodes, replicas or leaders change.
+An update failing on some shards only is reported as `SolrError::Partial`, listing the failed requests
+and documents which couldn't be routed to a shard.
+Ids may carry route keys, e.g. `tenant!id` or `tenant/4!user!id`, and `SolrCompositeIdRouter`
+computes the same hashes as Solr to find the shard a document belongs to.
+
+```ignore
+let solr = SolrCloudClient::new(&Url::parse("http://localhost:8983/solr/").unwrap(), "products");
+solr.add_many(&[&document1, &document2]);
+solr.commit();
+solr.query(&SolrQuery::new("*:*"));
+```
+
+## Errors
+
+`SolrError` tells transport failures and timeouts from unsuccessful HTTP statuses,
+errors reported by Solr, and requests or responses that couldn't be serialized or parsed.
+It implements `std::error::Error`, with the underlying error as its source.
+
+```ignore
+match solr.query(&query) {
+    Ok(response) => println!("Found {}", response.total),
+    Err(SolrError::Solr { code, msg, .. }) => println!("Solr rejected the query ({}): {}", code, msg),
+    Err(err) => println!("Error: {}", err)
+}
+```
+
 ## Indexing
 
 ### Adding new document to solr
@@ -60,7 +264,7 @@ fn main(){
     document.add_field("body", "In my younger and more vulnerable years..".to_string());
     match solr.add_and_commit(&document) {
         Ok(solr_response) => println!("{}", solr_response),
-        Err(solr_error) => println!("Status: {}, Message: {}", solr_error.status, solr_error.message)
+        Err(solr_error) => println!("Error: {}", solr_error)
     }
 }
 ```
@@ -87,7 +291,7 @@ document2.add_field("body", "Call me Ishmael".to_string());
 
 match solr.add_many_and_commit(vec!(&document1, &document2)) {
     Ok(solr_response) => println!("{}", solr_response),
-    Err(solr_error) => println!("Status: {}, Message: {}", solr_error.status, solr_error.message)
+    Err(solr_error) => println!("Error: {}", solr_error)
 }
 ```
 
@@ -111,7 +315,7 @@ match solr.query(&query) {
             println!("{}", item);
         }
     }
-    Err(solr_error) => println!("Status: {}, Message: {}", solr_error.status, solr_error.message)
+    Err(solr_error) => println!("Error: {}", solr_error)
 }
 ```
 
@@ -135,6 +339,60 @@ let query = SolrQuery::new("*:*")
     .set_filters(["type:Person", "class:AR"]);
 ```
 
+### Request handlers
+Queries are sent to `/select` and updates to `/update` by default. Other handlers can be
+chosen per query or update, and handlers dispatched by name selected with `qt`.
+
+```ignore
+solr.query(&SolrQuery::new("rust").handler("/search"));
+solr.add_with_options(&document, &SolrUpdateOptions::new().handler("/update/json/docs"));
+```
+
+### Long queries
+Queries with parameters longer than 4096 bytes once encoded, e.g. filters on hundreds of ids,
+are sent with POST as a form instead of in the URL. The limit can be set on the builder,
+and a query can set its HTTP method explicitly.
+
+```ignore
+let solr = SolrClient::builder(&url).max_get_query_length(2048).build();
+solr.query(&SolrQuery::new("*:*").add_filter(&ids_filter).method(HttpMethod::Post));
+```
+
+### JSON Request API
+`query_json` posts a query to `/query` as a JSON body instead of URL parameters.
+It can carry boolean queries nesting other queries, and JSON facets.
+
+```ignore
+let query = SolrQuery::new("*:*")
+    .json_query(&SolrBoolQuery::new()
+        .must("title:rust")
+        .should(&SolrBoolQuery::new().must("author:klabnik").must_not("year:[* TO 2015]"))
+        .filter("type:book"))
+    .add_json_facet("genres", Json::from_str(r#"{"type":"terms","field":"genre"}"#).unwrap());
+solr.query_json(&query);
+```
+
+### Real-time get
+Documents can be fetched by unique key from the `/get` handler, including updates
+not committed yet.
+
+```ignore
+let document: Option<SolrDocument> = solr.get_by_id("1").unwrap();
+let documents = solr.get_by_ids(&["1", "2"]).unwrap().items;
+```
+
+### Reading documents
+Field values of found documents can be read by name, with typed accessors.
+Multi-valued fields are returned by `get_all`.
+
+```ignore
+for doc in solr_response.items.iter() {
+    let id: Option<&str> = doc.get_str("id");
+    let year: Option<i64> = doc.get_i64("year");
+    let tags: Vec<&SolrValue> = doc.get_all("tags");
+}
+```
+
 ### Pagination
 
 ```ignore
@@ -149,6 +407,107 @@ solr.delete_by_id("99");
 ```
 
 Note that `delete_by_id` commits automatically after every delete request
+
+### Batching update commands
+
+Adds, deletes, commits and optimizes can be combined into a single request.
+Solr executes the commands in the order they were added.
+
+```ignore
+let mut batch = SolrUpdateBatch::new();
+batch.add(&document1);
+batch.delete_by_id("99");
+batch.delete_by_query("type:Draft");
+batch.commit();
+solr.update_batch(&batch);
+```
+
+### Streaming large batches
+
+`add_stream` encodes documents from an iterator while sending them (chunked transfer),
+so memory use stays flat regardless of the number of documents.
+
+```ignore
+let documents = (0..1_000_000).map(|i| {
+    let mut document = SolrDocument::new();
+    document.add_field("id", &i.to_string());
+    document
+});
+solr.add_stream_with_options(documents, &SolrUpdateOptions::new().commit(true));
+```
+
+### Atomic updates and boosts
+
+```ignore
+let mut document = SolrDocument::new();
+document.add_field("id", "1");
+document.add_update("views", AtomicUpdate::Inc, SolrValue::I64(1));
+document.add_update("tags", AtomicUpdate::Add, SolrValue::String("classic".to_string()));
+document.add_update("draft", AtomicUpdate::Set, SolrValue::Null);
+solr.add(&document);
+```
+
+Setting a field to `SolrValue::Null` removes it. Document boosts are sent with `add`
+and `add_many` in both formats, but only with the XML format by `add_stream`.
+
+### XML update format
+
+Update requests are sent as JSON by default. Older Solr installs and update processors
+expecting XML messages can be served by switching the client format.
+Documents, delete requests and update batches can also be serialized with `to_xml`.
+
+```ignore
+let mut solr = SolrClient::new(&url);
+solr.set_update_format(UpdateFormat::Xml);
+solr.add_and_commit(&document);
+```
+
+### CSV
+
+CSV data can be indexed from any `Read` implementation, and query results can be
+written in CSV format to any `Write` implementation.
+
+```ignore
+let csv = File::open("books.csv").unwrap();
+let csv_options = SolrCsvOptions::new()
+    .separator(';')
+    .split_field("tags", '|')
+    .add_map("N/A", "");
+solr.add_csv_with_options(csv, &csv_options, &SolrUpdateOptions::new().commit(true));
+
+let mut out = File::create("export.csv").unwrap();
+solr.query_csv(&SolrQuery::new("*:*").rows(1000), &mut out);
+```
+
+### Bulk indexing
+
+`BulkIndexer` buffers documents and sends them in batches from background threads.
+Batches are flushed by document count, estimated JSON size or time, and retried on failure.
+
+```ignore
+let options = BulkIndexerOptions::new()
+    .max_documents(1000)
+    .workers(4)
+    .on_batch(|outcome| if outcome.result.is_err() { println!("Batch {} failed", outcome.batch) });
+let indexer = BulkIndexer::new(solr, options);
+for document in documents {
+    indexer.add(document);
+}
+let totals = indexer.finish();
+println!("Indexed {} documents, {} failed", totals.indexed, totals.failed);
+```
+
+### Update options
+
+Every update method has a `*_with_options` counterpart accepting `SolrUpdateOptions`,
+which control commit behaviour and other update request parameters.
+
+```ignore
+let options = SolrUpdateOptions::new().commit_within(5000).overwrite(false);
+solr.add_many_with_options(&[&document1, &document2], &options);
+solr.commit_with_options(&SolrUpdateOptions::new().soft_commit(true));
+solr.optimize_with_options(&SolrUpdateOptions::new().max_segments(4));
+```
 */
 
 #![crate_name="heliotrope"]
@@ -156,11 +515,32 @@ Note that `delete_by_id` commits automatically after every delete request
 extern crate rustc_serialize;
 extern crate url;
 extern crate hyper;
-
-pub use self::client::SolrClient;
-pub use self::document::SolrDocument;
-pub use self::query::{SolrQuery, SortClause, SortOrder};
-pub use self::request::SolrDeleteRequest;
+extern crate openssl;
+extern crate flate2;
+extern crate futures;
+extern crate hyper_async;
+extern crate tokio_core;
+
+pub use self::async_client::{SolrAsyncClient, SolrFuture};
+pub use self::auth::SolrCredentials;
+pub use self::bulk::{BulkIndexer, BulkIndexerOptions, BulkIndexerTotals, BatchOutcome};
+pub use self::client::{SolrClient, SolrClientBuilder};
+pub use self::cloud::{SolrCloudClient, SolrClusterState, SolrReplica, SolrShard};
+pub use self::csv::SolrCsvOptions;
+pub use self::xml::{ToXml, UpdateFormat};
+pub use self::document::{AtomicUpdate, SolrDocument, SolrField, SolrFields, SolrValue};
+pub use self::lb::SolrLbClient;
+pub use self::proxy::SolrProxyConfig;
+pub use self::query::{SolrBoolQuery, SolrQuery, SortClause, SortOrder};
+pub use self::request::{SolrDeleteRequest, SolrUpdateBatch, SolrUpdateOptions};
+pub use self::retry::SolrRetryPolicy;
+pub use self::router::{SolrCompositeIdRouter, murmur3_32};
+pub use self::tls::SolrTlsConfig;
+pub use self::transport::{AsyncTransport, HttpBody, HttpFuture, HttpMethod, HttpRequest, HttpResponse, RecordedRequest, RecordingTransport, Transport,
+                          connect_timeout_error};
+pub use self::http_utils::{HyperTransport, HyperTransportConfig};
+pub use self::async_transport::HyperAsyncTransport;
+pub use self::response::SolrError;
 
 mod http_utils;
 mod document;
@@ -168,3 +548,17 @@ mod query;
 mod request;
 mod response;
 mod client;
+mod bulk;
+mod stream;
+mod csv;
+mod xml;
+mod transport;
+mod retry;
+mod auth;
+mod tls;
+mod proxy;
+mod async_client;
+mod async_transport;
+mod lb;
+mod cloud;
+mod router;
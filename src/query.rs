@@ -1,3 +1,9 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+use rustc_serialize::json::{Json, ToJson};
+
+use transport::HttpMethod;
+
 static DEFAULT_START: u64 = 0;
 static DEFAULT_ROWS: u32 = 10;
 
@@ -16,7 +22,15 @@ pub struct SolrQuery {
     filters: Option<Vec<String>>,
     sorts: Option<Vec<SortClause>>,
     start: u64,
-    rows: u32
+    rows: u32,
+    time_allowed: Option<u64>,
+    timeout: Option<Duration>,
+    headers: Vec<(String, String)>,
+    handler: Option<String>,
+    qt: Option<String>,
+    method: Option<HttpMethod>,
+    json_query: Option<Json>,
+    json_facets: Vec<(String, Json)>
 }
 
 impl SolrQuery {
@@ -27,7 +41,15 @@ impl SolrQuery {
             filters: None,
             sorts: None,
             start: 0,
-            rows: DEFAULT_ROWS }
+            rows: DEFAULT_ROWS,
+            time_allowed: None,
+            timeout: None,
+            headers: Vec::new(),
+            handler: None,
+            qt: None,
+            method: None,
+            json_query: None,
+            json_facets: Vec::new() }
 
     }
 
@@ -129,6 +151,125 @@ impl SolrQuery {
         solr_query
     }
 
+    /// Sets time in milliseconds Solr may spend searching (timeAllowed).
+    /// When it elapses, Solr returns partial results, see `SolrQueryResponse::partial_results`.
+    pub fn time_allowed(&self, milliseconds: u64) -> SolrQuery {
+        let mut solr_query = self.clone();
+        solr_query.time_allowed = Some(milliseconds);
+        solr_query
+    }
+
+    /// Overrides the client's read and write timeouts for this query.
+    /// Unlike `time_allowed`, this is enforced by the client and isn't sent to Solr.
+    pub fn timeout(&self, timeout: Duration) -> SolrQuery {
+        let mut solr_query = self.clone();
+        solr_query.timeout = Some(timeout);
+        solr_query
+    }
+
+    /// Returns the timeout overriding the client's timeouts, if set
+    pub fn get_timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Adds an HTTP header sent with this query,
+    /// replacing the client's default header with the same name
+    pub fn add_header(&self, name: &str, value: &str) -> SolrQuery {
+        let mut solr_query = self.clone();
+        solr_query.headers.push((name.to_string(), value.to_string()));
+        solr_query
+    }
+
+    /// Returns HTTP headers sent with this query
+    pub fn get_headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    /// Sends this query to a request handler other than `/select`, e.g. `/search` or `/browse`.
+    /// The path is relative to the client's base URL, with or without the leading slash.
+    pub fn handler(&self, path: &str) -> SolrQuery {
+        let mut solr_query = self.clone();
+        solr_query.handler = Some(path.to_string());
+        solr_query
+    }
+
+    /// Returns the request handler path, if other than `/select`
+    pub fn get_handler(&self) -> Option<&str> {
+        self.handler.as_ref().map(|handler| &handler[..])
+    }
+
+    /// Selects a request handler by name with the qt parameter,
+    /// which the handler at `/select` dispatches on when `handleSelect` is enabled
+    pub fn qt(&self, qt: &str) -> SolrQuery {
+        let mut solr_query = self.clone();
+        solr_query.qt = Some(qt.to_string());
+        solr_query
+    }
+
+    /// Sends this query with a given HTTP method. By default queries are sent with GET,
+    /// or with POST when their parameters are longer than the client's `max_get_query_length`.
+    pub fn method(&self, method: HttpMethod) -> SolrQuery {
+        let mut solr_query = self.clone();
+        solr_query.method = Some(method);
+        solr_query
+    }
+
+    /// Returns the HTTP method this query is sent with, if set
+    pub fn get_method(&self) -> Option<HttpMethod> {
+        self.method
+    }
+
+    /// Sets a query of the JSON Request API replacing the query string, e.g. a `SolrBoolQuery`.
+    /// It's only sent by `SolrClient::query_json`.
+    pub fn json_query<T: ToJson + ?Sized>(&self, query: &T) -> SolrQuery {
+        let mut solr_query = self.clone();
+        solr_query.json_query = Some(query.to_json());
+        solr_query
+    }
+
+    /// Adds a facet of the JSON Facet API, e.g. `{"type":"terms","field":"genre"}`.
+    /// It's only sent by `SolrClient::query_json`.
+    pub fn add_json_facet(&self, name: &str, facet: Json) -> SolrQuery {
+        let mut solr_query = self.clone();
+        solr_query.json_facets.push((name.to_string(), facet));
+        solr_query
+    }
+
+    /// Converts this query to a body of the JSON Request API, with parameters
+    /// not covered by it, like timeAllowed, under `params`
+    pub fn to_json(&self) -> Json {
+        let mut body = BTreeMap::new();
+        body.insert("query".to_string(), self.json_query.clone().unwrap_or_else(|| self.query.to_json()));
+        if let Some(ref fields) = self.fields {
+            body.insert("fields".to_string(), fields.to_json());
+        }
+        if let Some(ref filters) = self.filters {
+            body.insert("filter".to_string(), filters.to_json());
+        }
+        if let Some(ref sorts) = self.sorts {
+            let sorts: Vec<String> = sorts.iter().map(|sort| sort.to_url_param()).collect();
+            body.insert("sort".to_string(), sorts.join(", ").to_json());
+        }
+        if self.start != DEFAULT_START {
+            body.insert("offset".to_string(), self.start.to_json());
+        }
+        body.insert("limit".to_string(), self.rows.to_json());
+        if !self.json_facets.is_empty() {
+            let facets: BTreeMap<String, Json> = self.json_facets.iter().cloned().collect();
+            body.insert("facet".to_string(), Json::Object(facets));
+        }
+        let mut params = BTreeMap::new();
+        params.insert("wt".to_string(), "json".to_json());
+        if let Some(time_allowed) = self.time_allowed {
+            params.insert("timeAllowed".to_string(), time_allowed.to_json());
+        }
+        if let Some(ref qt) = self.qt {
+            params.insert("qt".to_string(), qt.to_json());
+        }
+        body.insert("params".to_string(), Json::Object(params));
+        Json::Object(body)
+    }
+
     /// Converts this query to a vector of pairs, suitable for URL percent encoding
     pub fn to_pairs(&self) -> Vec<(String, String)> {
         // usually will be wt, q and something else
@@ -182,10 +323,79 @@ impl SolrQuery {
         if self.rows != DEFAULT_ROWS {
             vec.push(("rows".to_string(), self.rows.to_string()));
         }
+
+        if let Some(time_allowed) = self.time_allowed {
+            vec.push(("timeAllowed".to_string(), time_allowed.to_string()));
+        }
+
+        if let Some(ref qt) = self.qt {
+            vec.push(("qt".to_string(), qt.clone()));
+        }
         vec
     }
 }
 
+/// Boolean query of the JSON Request API, combining clauses which are query strings
+/// or nested boolean queries. Clauses are added to copies, so a query can be shared as
+/// the base of several others.
+#[derive(Clone, Debug, Default)]
+pub struct SolrBoolQuery {
+    must: Vec<Json>,
+    should: Vec<Json>,
+    must_not: Vec<Json>,
+    filter: Vec<Json>
+}
+
+impl SolrBoolQuery {
+    /// Creates a boolean query without clauses
+    pub fn new() -> SolrBoolQuery {
+        SolrBoolQuery::default()
+    }
+
+    /// Adds a clause matching documents must match, contributing to the score
+    pub fn must<T: ToJson + ?Sized>(&self, clause: &T) -> SolrBoolQuery {
+        let mut query = self.clone();
+        query.must.push(clause.to_json());
+        query
+    }
+
+    /// Adds a clause matching documents should match, contributing to the score
+    pub fn should<T: ToJson + ?Sized>(&self, clause: &T) -> SolrBoolQuery {
+        let mut query = self.clone();
+        query.should.push(clause.to_json());
+        query
+    }
+
+    /// Adds a clause matching documents must not match
+    pub fn must_not<T: ToJson + ?Sized>(&self, clause: &T) -> SolrBoolQuery {
+        let mut query = self.clone();
+        query.must_not.push(clause.to_json());
+        query
+    }
+
+    /// Adds a clause matching documents must match, without contributing to the score
+    pub fn filter<T: ToJson + ?Sized>(&self, clause: &T) -> SolrBoolQuery {
+        let mut query = self.clone();
+        query.filter.push(clause.to_json());
+        query
+    }
+}
+
+impl ToJson for SolrBoolQuery {
+    fn to_json(&self) -> Json {
+        let mut clauses = BTreeMap::new();
+        for &(name, values) in &[("must", &self.must), ("should", &self.should),
+                                    ("must_not", &self.must_not), ("filter", &self.filter)] {
+            if !values.is_empty() {
+                clauses.insert(name.to_string(), values.to_json());
+            }
+        }
+        let mut query = BTreeMap::new();
+        query.insert("bool".to_string(), Json::Object(clauses));
+        Json::Object(query)
+    }
+}
+
 /// Represents sort ordering for a field
 #[derive(Clone, Copy)]
 pub enum SortOrder {
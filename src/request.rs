@@ -1,4 +1,7 @@
+use std::time::Duration;
 use rustc_serialize::{Encodable, Encoder};
+use document::SolrDocument;
+use xml::{escape, ToXml};
 
 /// Represents a document(s) delete request
 #[derive(Debug)]
@@ -20,37 +23,336 @@ impl SolrDeleteRequest {
     pub fn from_query(query: &str) -> SolrDeleteRequest {
         SolrDeleteRequest {ids: None, query: Some(query.to_string())}
     }
-}
 
-impl Encodable for SolrDeleteRequest {
-    fn encode<E: Encoder>(&self, e: &mut E) -> Result<(), E::Error> {
-        let result = if let Some(ref ids) = self.ids {
-            e.emit_struct("SolrDeleteRequest", 1, |e| {
-                e.emit_struct_field("delete", 0, |e| {
-                    e.emit_seq(ids.len(), |e| {
-                        for (i,id) in ids.iter().enumerate() {
-                             e.emit_seq_elt(i, |e| {
-                                e.emit_struct("id_struct", 1, |e| {
-                                    e.emit_struct_field("id", 0, |e| id.encode(e))
-                                })
-                             });
-                        }
-                        Ok(())
-                    })
-                })
+    // Encodes the value of the "delete" command, shared with SolrUpdateBatch
+    fn encode_command<E: Encoder>(&self, e: &mut E) -> Result<(), E::Error> {
+        if let Some(ref ids) = self.ids {
+            e.emit_seq(ids.len(), |e| {
+                for (i,id) in ids.iter().enumerate() {
+                     try!(e.emit_seq_elt(i, |e| {
+                        e.emit_struct("id_struct", 1, |e| {
+                            e.emit_struct_field("id", 0, |e| id.encode(e))
+                        })
+                     }));
+                }
+                Ok(())
             })
         } else if let Some(ref query) = self.query {
-           e.emit_struct("SolrDeleteRequest", 1, |e| {
-                e.emit_struct_field("delete", 0, |e| {
-                    e.emit_struct("id_struct", 1, |e| {
-                        e.emit_struct_field("query", 0, |e| query.encode(e));
-                        Ok(())
-                    })
-                })
-            }) 
+            e.emit_struct("id_struct", 1, |e| {
+                e.emit_struct_field("query", 0, |e| query.encode(e))
+            })
         } else {
             unreachable!()
-        };
-        result
+        }
+    }
+}
+
+impl Encodable for SolrDeleteRequest {
+    fn encode<E: Encoder>(&self, e: &mut E) -> Result<(), E::Error> {
+        e.emit_struct("SolrDeleteRequest", 1, |e| {
+            e.emit_struct_field("delete", 0, |e| self.encode_command(e))
+        })
+    }
+}
+
+impl ToXml for SolrDeleteRequest {
+    fn to_xml(&self) -> String {
+        let mut xml = String::from("<delete>");
+        if let Some(ref ids) = self.ids {
+            for id in ids.iter() {
+                xml.push_str(&format!("<id>{}</id>", escape(id)));
+            }
+        } else if let Some(ref query) = self.query {
+            xml.push_str(&format!("<query>{}</query>", escape(query)));
+        }
+        xml.push_str("</delete>");
+        xml
+    }
+}
+
+// A single command of SolrUpdateBatch
+#[derive(Debug)]
+enum SolrUpdateCommand<'a> {
+    Add(&'a SolrDocument),
+    Delete(SolrDeleteRequest),
+    Commit,
+    Optimize,
+    Rollback
+}
+
+/// Represents a batch of update commands (add, delete, commit, optimize)
+/// to be sent to Solr in a single request.
+/// Commands are executed by Solr in the order they were added.
+#[derive(Debug)]
+pub struct SolrUpdateBatch<'a> {
+    commands: Vec<SolrUpdateCommand<'a>>
+}
+
+impl<'a> SolrUpdateBatch<'a> {
+    /// Creates an empty batch
+    pub fn new() -> SolrUpdateBatch<'a> {
+        SolrUpdateBatch { commands: Vec::new() }
+    }
+
+    /// Adds a document to the batch
+    pub fn add(&mut self, document: &'a SolrDocument) {
+        self.commands.push(SolrUpdateCommand::Add(document));
+    }
+
+    /// Adds multiple documents to the batch
+    pub fn add_many(&mut self, documents: &[&'a SolrDocument]) {
+        self.commands.extend(documents.iter().map(|&d| SolrUpdateCommand::Add(d)));
+    }
+
+    /// Adds a delete request to the batch
+    pub fn delete(&mut self, request: SolrDeleteRequest) {
+        self.commands.push(SolrUpdateCommand::Delete(request));
+    }
+
+    /// Adds a delete of a single document by a unique ID
+    pub fn delete_by_id(&mut self, id: &str) {
+        self.delete(SolrDeleteRequest::from_id(id));
+    }
+
+    /// Adds a delete of documents by a query
+    pub fn delete_by_query(&mut self, query: &str) {
+        self.delete(SolrDeleteRequest::from_query(query));
+    }
+
+    /// Adds a commit command, committing everything preceding it
+    pub fn commit(&mut self) {
+        self.commands.push(SolrUpdateCommand::Commit);
+    }
+
+    /// Adds an optimize command
+    pub fn optimize(&mut self) {
+        self.commands.push(SolrUpdateCommand::Optimize);
+    }
+
+    /// Adds a rollback command, discarding uncommitted changes preceding it
+    pub fn rollback(&mut self) {
+        self.commands.push(SolrUpdateCommand::Rollback);
+    }
+
+    /// Number of commands in the batch
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    /// Returns true if the batch has no commands
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+}
+
+impl<'a> Default for SolrUpdateBatch<'a> {
+    fn default() -> SolrUpdateBatch<'a> {
+        SolrUpdateBatch::new()
+    }
+}
+
+// Solr JSON update syntax allows repeating command names in a single object, e.g.
+// {"add":{"doc":{..}},"add":{"doc":{..}},"delete":{"query":".."},"commit":{}}
+impl<'a> Encodable for SolrUpdateBatch<'a> {
+    fn encode<E: Encoder>(&self, e: &mut E) -> Result<(), E::Error> {
+        e.emit_struct("SolrUpdateBatch", self.commands.len(), |e| {
+            for (i, command) in self.commands.iter().enumerate() {
+                try!(match *command {
+                    SolrUpdateCommand::Add(document) => e.emit_struct_field("add", i, |e| {
+                        match document.boost() {
+                            Some(boost) => e.emit_struct("add", 2, |e| {
+                                try!(e.emit_struct_field("boost", 0, |e| boost.encode(e)));
+                                e.emit_struct_field("doc", 1, |e| document.encode(e))
+                            }),
+                            None => e.emit_struct("add", 1, |e| e.emit_struct_field("doc", 0, |e| document.encode(e)))
+                        }
+                    }),
+                    SolrUpdateCommand::Delete(ref request) => e.emit_struct_field("delete", i, |e| request.encode_command(e)),
+                    SolrUpdateCommand::Commit => e.emit_struct_field("commit", i, |e| e.emit_struct("commit", 0, |_| Ok(()))),
+                    SolrUpdateCommand::Optimize => e.emit_struct_field("optimize", i, |e| e.emit_struct("optimize", 0, |_| Ok(()))),
+                    SolrUpdateCommand::Rollback => e.emit_struct_field("rollback", i, |e| e.emit_struct("rollback", 0, |_| Ok(())))
+                });
+            }
+            Ok(())
+        })
+    }
+}
+
+// Solr XML update syntax allows several commands wrapped in an <update> element
+impl<'a> ToXml for SolrUpdateBatch<'a> {
+    fn to_xml(&self) -> String {
+        let mut xml = String::from("<update>");
+        for command in self.commands.iter() {
+            match *command {
+                SolrUpdateCommand::Add(document) => xml.push_str(&format!("<add>{}</add>", document.to_xml())),
+                SolrUpdateCommand::Delete(ref request) => xml.push_str(&request.to_xml()),
+                SolrUpdateCommand::Commit => xml.push_str("<commit/>"),
+                SolrUpdateCommand::Optimize => xml.push_str("<optimize/>"),
+                SolrUpdateCommand::Rollback => xml.push_str("<rollback/>")
+            }
+        }
+        xml.push_str("</update>");
+        xml
+    }
+}
+
+/// Represents parameters of an update request (add, delete, commit, optimize):
+/// commit and optimize flags, update chain, request timeout, headers and handler.
+#[derive(Clone, Debug, Default)]
+pub struct SolrUpdateOptions {
+    commit: bool,
+    soft_commit: bool,
+    wait_searcher: Option<bool>,
+    expunge_deletes: bool,
+    commit_within: Option<u64>,
+    overwrite: Option<bool>,
+    update_chain: Option<String>,
+    optimize: bool,
+    max_segments: Option<u32>,
+    timeout: Option<Duration>,
+    headers: Vec<(String, String)>,
+    handler: Option<String>
+}
+
+impl SolrUpdateOptions {
+    /// Creates update options with Solr defaults
+    pub fn new() -> SolrUpdateOptions {
+        SolrUpdateOptions::default()
+    }
+
+    /// Commits after the update is processed (commit)
+    pub fn commit(&self, commit: bool) -> SolrUpdateOptions {
+        let mut options = self.clone();
+        options.commit = commit;
+        options
+    }
+
+    /// Performs a soft commit, making changes visible without flushing them to disk (softCommit)
+    pub fn soft_commit(&self, soft_commit: bool) -> SolrUpdateOptions {
+        let mut options = self.clone();
+        options.soft_commit = soft_commit;
+        options
+    }
+
+    /// Sets whether the commit blocks until a new searcher is opened (waitSearcher)
+    pub fn wait_searcher(&self, wait_searcher: bool) -> SolrUpdateOptions {
+        let mut options = self.clone();
+        options.wait_searcher = Some(wait_searcher);
+        options
+    }
+
+    /// Merges away segments with deleted documents on commit (expungeDeletes)
+    pub fn expunge_deletes(&self, expunge_deletes: bool) -> SolrUpdateOptions {
+        let mut options = self.clone();
+        options.expunge_deletes = expunge_deletes;
+        options
+    }
+
+    /// Asks Solr to commit the update within the given number of milliseconds (commitWithin)
+    pub fn commit_within(&self, millis: u64) -> SolrUpdateOptions {
+        let mut options = self.clone();
+        options.commit_within = Some(millis);
+        options
+    }
+
+    /// Sets whether added documents replace existing ones with the same unique key (overwrite)
+    pub fn overwrite(&self, overwrite: bool) -> SolrUpdateOptions {
+        let mut options = self.clone();
+        options.overwrite = Some(overwrite);
+        options
+    }
+
+    /// Sets the update request processor chain to use (update.chain)
+    pub fn update_chain(&self, chain: &str) -> SolrUpdateOptions {
+        let mut options = self.clone();
+        options.update_chain = Some(chain.to_string());
+        options
+    }
+
+    /// Optimizes the index after the update is processed (optimize)
+    pub fn optimize(&self, optimize: bool) -> SolrUpdateOptions {
+        let mut options = self.clone();
+        options.optimize = optimize;
+        options
+    }
+
+    /// Sets the number of segments the optimize merges down to (maxSegments)
+    pub fn max_segments(&self, max_segments: u32) -> SolrUpdateOptions {
+        let mut options = self.clone();
+        options.max_segments = Some(max_segments);
+        options
+    }
+
+    /// Overrides the client's read and write timeouts for this request.
+    /// It's enforced by the client and isn't sent to Solr.
+    pub fn timeout(&self, timeout: Duration) -> SolrUpdateOptions {
+        let mut options = self.clone();
+        options.timeout = Some(timeout);
+        options
+    }
+
+    /// Returns the timeout overriding the client's timeouts, if set
+    pub fn get_timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Adds an HTTP header sent with this request,
+    /// replacing the client's default header with the same name
+    pub fn add_header(&self, name: &str, value: &str) -> SolrUpdateOptions {
+        let mut options = self.clone();
+        options.headers.push((name.to_string(), value.to_string()));
+        options
+    }
+
+    /// Returns HTTP headers sent with this request
+    pub fn get_headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    /// Sends this request to an update handler other than `/update`, e.g. `/update/json/docs`.
+    /// The path is relative to the client's base URL, with or without the leading slash.
+    pub fn handler(&self, path: &str) -> SolrUpdateOptions {
+        let mut options = self.clone();
+        options.handler = Some(path.to_string());
+        options
+    }
+
+    /// Returns the update handler path, if other than `/update`
+    pub fn get_handler(&self) -> Option<&str> {
+        self.handler.as_ref().map(|handler| &handler[..])
+    }
+
+    /// Converts these options to a vector of pairs, suitable for URL percent encoding
+    pub fn to_pairs(&self) -> Vec<(String, String)> {
+        let mut vec = Vec::with_capacity(3);
+        vec.push(("wt".to_string(), "json".to_string()));
+        if self.commit {
+            vec.push(("commit".to_string(), "true".to_string()));
+        }
+        if self.optimize {
+            vec.push(("optimize".to_string(), "true".to_string()));
+        }
+        if let Some(max_segments) = self.max_segments {
+            vec.push(("maxSegments".to_string(), max_segments.to_string()));
+        }
+        if self.soft_commit {
+            vec.push(("softCommit".to_string(), "true".to_string()));
+        }
+        if let Some(wait_searcher) = self.wait_searcher {
+            vec.push(("waitSearcher".to_string(), wait_searcher.to_string()));
+        }
+        if self.expunge_deletes {
+            vec.push(("expungeDeletes".to_string(), "true".to_string()));
+        }
+        if let Some(commit_within) = self.commit_within {
+            vec.push(("commitWithin".to_string(), commit_within.to_string()));
+        }
+        if let Some(overwrite) = self.overwrite {
+            vec.push(("overwrite".to_string(), overwrite.to_string()));
+        }
+        if let Some(ref chain) = self.update_chain {
+            vec.push(("update.chain".to_string(), chain.clone()));
+        }
+        vec
     }
 }
@@ -6,30 +6,133 @@ mod update;
 mod query;
 mod ping;
 
+use std::error::Error;
+use std::fmt;
+use std::io;
 use rustc_serialize::{json, Decodable, Decoder};
 use rustc_serialize::json::Json;
 
+/// Error of a Solr request.
+/// Variants caused by a sent request record the number of times it was sent,
+/// which is more than 1 when it was retried.
+#[derive(Debug)]
+pub enum SolrError {
+    /// The request couldn't be sent or the response couldn't be read
+    Transport { source: io::Error, attempts: u32 },
+    /// The connection or the response didn't arrive within the configured timeout.
+    /// `connect` tells if the connection couldn't be established in time.
+    Timeout { source: io::Error, connect: bool, attempts: u32 },
+    /// Solr answered with an unsuccessful HTTP status, without an error in its response
+    Http { status: u16, body: String, attempts: u32 },
+    /// Solr reported an error
+    Solr {
+        /// HTTP status of the response
+        status: u16,
+        /// Error code reported by Solr, usually the same as the status
+        code: i32,
+        /// Error message
+        msg: String,
+        /// Pairs of error metadata, e.g. `error-class` and `root-error-class`
+        metadata: Vec<(String, String)>,
+        /// Java stack trace, reported for server errors
+        trace: Option<String>,
+        attempts: u32
+    },
+    /// The request couldn't be serialized
+    Serialization(json::EncoderError),
+    /// The response couldn't be parsed
+    Parse { message: String, source: Option<Box<dyn Error + Send + Sync>> },
+    /// No node could serve the request, e.g. all nodes are down or a shard has no leader
+    Unavailable(String),
+    /// An update split across shards failed on some of them and was applied on the others.
+    /// Lists URLs the failed requests were sent to, or ids of documents which couldn't be routed
+    /// to a shard, with their errors.
+    Partial { failures: Vec<(String, SolrError)> }
+}
+
+impl SolrError {
+    /// Creates a parse error without a source error
+    pub fn parse(message: &str) -> SolrError {
+        SolrError::Parse { message: message.to_string(), source: None }
+    }
+
+    /// Returns the HTTP status of the response, if Solr answered
+    pub fn status(&self) -> Option<u16> {
+        match *self {
+            SolrError::Http { status, .. } | SolrError::Solr { status, .. } => Some(status),
+            _ => None
+        }
+    }
+
+    /// Returns the number of times the request was sent
+    pub fn attempts(&self) -> u32 {
+        match *self {
+            SolrError::Transport { attempts, .. } | SolrError::Timeout { attempts, .. } |
+            SolrError::Http { attempts, .. } | SolrError::Solr { attempts, .. } => attempts,
+            _ => 1
+        }
+    }
 
-/// SolrError
-pub struct SolrError {
-    /// HTTP status.
-    /// When failed to connect, it will be 0 (zero).
-    pub status: i32,
-    /// Time it took to execute the request in milliseconds
-    pub time: i32,
-    /// Detailed error message
-    pub message: String
+    /// Sets the number of times the request was sent
+    pub fn with_attempts(mut self, sent: u32) -> SolrError {
+        match self {
+            SolrError::Transport { ref mut attempts, .. } | SolrError::Timeout { ref mut attempts, .. } |
+            SolrError::Http { ref mut attempts, .. } | SolrError::Solr { ref mut attempts, .. } => *attempts = sent,
+            _ => {}
+        }
+        self
+    }
+}
+
+impl fmt::Display for SolrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SolrError::Transport { ref source, .. } => write!(f, "Http error: {}", source),
+            SolrError::Timeout { ref source, .. } => write!(f, "Timed out: {}", source),
+            SolrError::Http { status, ref body, .. } => write!(f, "HTTP status {}: {}", status, body),
+            SolrError::Solr { code, ref msg, .. } => write!(f, "Solr error {}: {}", code, msg),
+            SolrError::Serialization(ref source) => write!(f, "Error serializing request: {}", source),
+            SolrError::Parse { ref message, source: Some(ref source) } => write!(f, "{}: {}", message, source),
+            SolrError::Parse { ref message, source: None } => write!(f, "{}", message),
+            SolrError::Unavailable(ref message) => write!(f, "{}", message),
+            SolrError::Partial { ref failures } => {
+                try!(write!(f, "Update failed on {} shards", failures.len()));
+                for (url, err) in failures {
+                    try!(write!(f, "; {}: {}", url, err));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for SolrError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            SolrError::Transport { ref source, .. } | SolrError::Timeout { ref source, .. } => Some(source),
+            SolrError::Serialization(ref source) => Some(source),
+            SolrError::Parse { source: Some(ref source), .. } => Some(&**source),
+            _ => None
+        }
+    }
 }
 
 impl Decodable for SolrError {
     fn decode<D: Decoder>(d: &mut D) -> Result<SolrError, D::Error> {
         d.read_struct("root", 0, |d| {
             d.read_struct_field("error", 0, |d| {
-                Ok(SolrError{
-                    message: try!(d.read_struct_field("msg", 0, Decodable::decode)),
-                    status: try!(d.read_struct_field("code", 1, Decodable::decode)),
-                    // TODO: implement time parsing from request header
-                    time: 0})
+                let code: i32 = try!(d.read_struct_field("code", 1, Decodable::decode));
+                let metadata: Option<Vec<String>> = try!(d.read_struct_field("metadata", 2, Decodable::decode));
+                Ok(SolrError::Solr {
+                    status: code as u16,
+                    code,
+                    msg: try!(d.read_struct_field("msg", 0, Decodable::decode)),
+                    metadata: metadata.unwrap_or_default().chunks(2)
+                        .map(|pair| (pair[0].clone(), pair.get(1).cloned().unwrap_or_default()))
+                        .collect(),
+                    trace: try!(d.read_struct_field("trace", 3, Decodable::decode)),
+                    attempts: 1
+                })
             })
         })
     }
@@ -51,7 +51,7 @@ impl SolrPingResponse {
         if error.len() == 0 {
             Ok(response)
         } else {
-            Err(SolrError{time: 0, status: 0, message: error})
+            Err(SolrError::parse(&error))
         }
     }
 }
@@ -1,6 +1,6 @@
 use rustc_serialize::{json, Decodable, Decoder};
 use rustc_serialize::json::Json;
-use document::{SolrDocument, SolrField, SolrValue};
+use document::{SolrDocument, SolrValue};
 use response::SolrError;
 
 pub type SolrQueryResult = Result<SolrQueryResponse, SolrError>;
@@ -19,7 +19,10 @@ pub struct SolrQueryResponse {
     /// Rows offset (zero based)
     pub start: u64,
     /// Current page of found Solr documents
-    pub items: Vec<SolrDocument>
+    pub items: Vec<SolrDocument>,
+    /// True when Solr stopped searching after `timeAllowed` elapsed,
+    /// so the results may be incomplete
+    pub partial_results: bool
 }
 
 /* Example JSON of query response: 
@@ -48,7 +51,7 @@ pub struct SolrQueryResponse {
 impl SolrQueryResponse {
     /// Deserializes SolrQueryResponse from JSON string
     pub fn from_json_str(json_str: &str) -> SolrQueryResult {
-        let mut response = SolrQueryResponse{status: 0, time: 0, total: 0, start: 0, items: Vec::new() };
+        let mut response = SolrQueryResponse{status: 0, time: 0, total: 0, start: 0, items: Vec::new(), partial_results: false };
         let mut error: String = "".to_string();
         match Json::from_str(json_str) {
             Ok(json) => match json {
@@ -67,6 +70,9 @@ impl SolrQueryResponse {
                                 Some(status_json) => response.status = status_json.as_u64().unwrap() as u32,
                                 None => error = "SolrQueryResponse JSON parsing error (responseHeader): status not found".to_string()
                             }
+                            if let Some(partial_json) = rh.find("partialResults") {
+                                response.partial_results = partial_json.as_boolean().unwrap_or(false);
+                            }
                         },
                         None => error = "SolrQueryResponse JSON parsing error: responseHeader not found".to_string()
 
@@ -110,29 +116,40 @@ impl SolrQueryResponse {
         if error.len() == 0 {
             Ok(response)
         } else {
-            Err(SolrError{time: 0, status: 0, message: error})
+            Err(SolrError::parse(&error))
         }
     }
 
     fn parse_doc(doc_json: &Json) -> Result<SolrDocument, String> {
         match doc_json {
             & Json::Object(ref tm) => {
-                let mut doc = SolrDocument{fields: Vec::with_capacity(tm.len())};
+                let mut doc = SolrDocument::new();
                 for (k, json_v) in tm.iter() {
-                    let v = match json_v {
-                        & Json::I64(i64) => SolrValue::I64(i64),
-                        & Json::U64(u64) => SolrValue::U64(u64),
-                        & Json::F64(f64) => SolrValue::F64(f64),
-                        & Json::String(ref string) => SolrValue::String(string.clone()),
-                        & Json::Boolean(bool) => SolrValue::Boolean(bool),
-                        _ => SolrValue::Null
-                    };
-                    doc.fields.push(SolrField{name: k.clone(), value: v});
+                    match json_v {
+                        // multi-valued fields become several fields with the same name
+                        & Json::Array(ref values) => {
+                            for v in values.iter() {
+                                doc.add_value(k, SolrQueryResponse::parse_value(v));
+                            }
+                        },
+                        _ => doc.add_value(k, SolrQueryResponse::parse_value(json_v))
+                    }
                 }
                 Ok(doc)
             },
             _ => Err("SolrQueryResponse JSON parsing error (response => docs): doc is not an object".to_string())
         }
     }
+
+    fn parse_value(json_v: &Json) -> SolrValue {
+        match json_v {
+            & Json::I64(i64) => SolrValue::I64(i64),
+            & Json::U64(u64) => SolrValue::U64(u64),
+            & Json::F64(f64) => SolrValue::F64(f64),
+            & Json::String(ref string) => SolrValue::String(string.clone()),
+            & Json::Boolean(bool) => SolrValue::Boolean(bool),
+            _ => SolrValue::Null
+        }
+    }
 }
 
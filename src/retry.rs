@@ -0,0 +1,129 @@
+use std::cmp;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::io;
+use std::time::Duration;
+
+use transport::HttpResponse;
+
+/// Decides which failed requests SolrClient retries and how long it waits in between.
+/// Queries, pings and real-time gets are retried automatically, updates only when `retry_updates` is set.
+/// Streamed update bodies (`add_stream`, `add_csv`) are never retried.
+/// The number of attempts, the backoff and the retried statuses and error kinds can be changed.
+#[derive(Clone, Debug)]
+pub struct SolrRetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    jitter: bool,
+    statuses: Vec<u16>,
+    error_kinds: Vec<io::ErrorKind>,
+    retry_updates: bool
+}
+
+impl Default for SolrRetryPolicy {
+    fn default() -> SolrRetryPolicy {
+        SolrRetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            jitter: true,
+            statuses: vec![502, 503, 504],
+            error_kinds: vec![io::ErrorKind::ConnectionRefused, io::ErrorKind::ConnectionReset,
+                              io::ErrorKind::ConnectionAborted, io::ErrorKind::BrokenPipe,
+                              io::ErrorKind::UnexpectedEof],
+            retry_updates: false
+        }
+    }
+}
+
+impl SolrRetryPolicy {
+    /// Creates a policy making up to 3 attempts, waiting 100 ms before the first retry
+    /// and doubling the wait up to 5 s. It retries on 502, 503 and 504 statuses,
+    /// refused, reset and aborted connections, broken pipes and unexpected ends of responses.
+    pub fn new() -> SolrRetryPolicy {
+        SolrRetryPolicy::default()
+    }
+
+    /// Sets the maximum number of attempts, including the first one
+    pub fn max_attempts(&self, max_attempts: u32) -> SolrRetryPolicy {
+        let mut policy = self.clone();
+        policy.max_attempts = cmp::max(max_attempts, 1);
+        policy
+    }
+
+    /// Sets the wait before the first retry, doubled for every further retry
+    pub fn initial_backoff(&self, backoff: Duration) -> SolrRetryPolicy {
+        let mut policy = self.clone();
+        policy.initial_backoff = backoff;
+        policy
+    }
+
+    /// Sets the longest wait between attempts
+    pub fn max_backoff(&self, backoff: Duration) -> SolrRetryPolicy {
+        let mut policy = self.clone();
+        policy.max_backoff = backoff;
+        policy
+    }
+
+    /// Sets whether waits are randomized between half and the full backoff, enabled by default.
+    /// This keeps clients failing at the same time from retrying all at once.
+    pub fn jitter(&self, jitter: bool) -> SolrRetryPolicy {
+        let mut policy = self.clone();
+        policy.jitter = jitter;
+        policy
+    }
+
+    /// Sets the HTTP statuses which are retried
+    pub fn retry_statuses(&self, statuses: &[u16]) -> SolrRetryPolicy {
+        let mut policy = self.clone();
+        policy.statuses = statuses.to_vec();
+        policy
+    }
+
+    /// Sets the kinds of network errors which are retried
+    pub fn retry_error_kinds(&self, kinds: &[io::ErrorKind]) -> SolrRetryPolicy {
+        let mut policy = self.clone();
+        policy.error_kinds = kinds.to_vec();
+        policy
+    }
+
+    /// Sets whether update requests are retried too.
+    /// Retrying an update can apply it twice, e.g. incrementing a field twice.
+    pub fn retry_updates(&self, retry_updates: bool) -> SolrRetryPolicy {
+        let mut policy = self.clone();
+        policy.retry_updates = retry_updates;
+        policy
+    }
+
+    /// Returns the maximum number of attempts
+    pub fn get_max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Returns true if update requests are retried
+    pub fn retries_updates(&self) -> bool {
+        self.retry_updates
+    }
+
+    /// Returns true if a request with given result should be retried
+    pub fn should_retry(&self, result: &io::Result<HttpResponse>) -> bool {
+        match *result {
+            Ok(ref response) => self.statuses.contains(&response.status),
+            Err(ref err) => self.error_kinds.contains(&err.kind())
+        }
+    }
+
+    /// Returns the wait after given failed attempt, counted from 1
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 1u32.checked_shl(attempt.saturating_sub(1)).unwrap_or(u32::MAX);
+        let backoff = cmp::min(self.initial_backoff.checked_mul(factor).unwrap_or(self.max_backoff), self.max_backoff);
+        if self.jitter {
+            let half = backoff / 2;
+            let random = RandomState::new().build_hasher().finish();
+            half + Duration::from_nanos(random % (half.as_nanos() as u64 + 1))
+        } else {
+            backoff
+        }
+    }
+}
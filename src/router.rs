@@ -0,0 +1,150 @@
+use cloud::{SolrClusterState, SolrShard};
+use document::SolrDocument;
+
+/// Router computing hashes of document ids the way Solr's compositeId router does,
+/// to find the shard a document belongs to.
+/// An id may be prefixed with up to two route keys separated by `!`, e.g. `tenant!id`
+/// or `tenant!user!id`. Documents sharing a route key get the same top bits of the hash,
+/// 16 bits for a single key, 8 bits for each of two keys. The number of bits can be set
+/// with a `/bits` suffix, e.g. `tenant/4!id` to spread a tenant across 1/16th of the shards.
+#[derive(Clone, Debug)]
+pub struct SolrCompositeIdRouter {
+    id_field: String
+}
+
+impl SolrCompositeIdRouter {
+    /// Creates a router reading ids of documents from the `id` field
+    pub fn new() -> SolrCompositeIdRouter {
+        SolrCompositeIdRouter { id_field: "id".to_string() }
+    }
+
+    /// Sets the unique key field holding ids of documents
+    pub fn id_field(&self, id_field: &str) -> SolrCompositeIdRouter {
+        SolrCompositeIdRouter { id_field: id_field.to_string() }
+    }
+
+    /// Returns the id of a document, if it has one
+    pub fn document_id<'a>(&self, document: &'a SolrDocument) -> Option<&'a str> {
+        document.get_str(&self.id_field)
+    }
+
+    /// Returns the hash of a document id, including route keys
+    pub fn hash(&self, id: &str) -> i32 {
+        if !id.contains('!') {
+            return murmur3_32(id.as_bytes(), 0) as i32;
+        }
+        let parts = split_id(id);
+        // An id ending with `!` has an empty last part
+        let pieces = if id.ends_with('!') && parts.len() < 3 { parts.len() + 1 } else { parts.len() };
+        let mut bits = if pieces == 3 { [8, 8] } else { [16, 0] };
+        let hashes: Vec<u32> = (0..pieces).map(|i| {
+            let part = parts.get(i).cloned().unwrap_or("");
+            let part = match part.find('/') {
+                Some(position) if position > 0 && i < pieces - 1 => {
+                    bits[i] = parse_bits(&part[position + 1..]);
+                    &part[..position]
+                },
+                _ => part
+            };
+            murmur3_32(part.as_bytes(), 0)
+        }).collect();
+        let masks = bit_masks(pieces == 3, bits);
+        hashes.iter().zip(masks.iter()).fold(0, |hash, (part, mask)| hash | (part & mask)) as i32
+    }
+
+    /// Returns the active shard a document id is routed to,
+    /// none for collections not using hash ranges
+    pub fn shard_for_id<'a>(&self, state: &'a SolrClusterState, id: &str) -> Option<&'a SolrShard> {
+        state.shard_for_hash(self.hash(id))
+    }
+
+    /// Returns the active shard a document is routed to by its id
+    pub fn shard_for_document<'a>(&self, state: &'a SolrClusterState, document: &SolrDocument) -> Option<&'a SolrShard> {
+        self.document_id(document).and_then(|id| self.shard_for_id(state, id))
+    }
+}
+
+impl Default for SolrCompositeIdRouter {
+    fn default() -> SolrCompositeIdRouter {
+        SolrCompositeIdRouter::new()
+    }
+}
+
+/// MurmurHash3 x86 32-bit hash, as computed by Solr's `Hash.murmurhash3_x86_32`
+pub fn murmur3_32(data: &[u8], seed: u32) -> u32 {
+    const C1: u32 = 0xcc9e_2d51;
+    const C2: u32 = 0x1b87_3593;
+    let mut hash = seed;
+    let mut chunks = data.chunks_exact(4);
+    for chunk in &mut chunks {
+        let mut k = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        k = k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+        hash ^= k;
+        hash = hash.rotate_left(13).wrapping_mul(5).wrapping_add(0xe654_6b64);
+    }
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        let mut k = 0u32;
+        for (i, byte) in tail.iter().enumerate() {
+            k |= (*byte as u32) << (8 * i);
+        }
+        hash ^= k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+    }
+    hash ^= data.len() as u32;
+    hash ^= hash >> 16;
+    hash = hash.wrapping_mul(0x85eb_ca6b);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(0xc2b2_ae35);
+    hash ^ (hash >> 16)
+}
+
+// Splits an id into at most three parts like Solr does, ignoring separators after the second one
+// and a trailing separator
+fn split_id(id: &str) -> Vec<&str> {
+    let mut parts = Vec::with_capacity(3);
+    let first = match id.find('!') {
+        Some(first) => first,
+        None => return vec![id]
+    };
+    parts.push(&id[..first]);
+    let rest = &id[first + 1..];
+    if rest.is_empty() {
+        return parts;
+    }
+    match rest.find('!') {
+        None => parts.push(rest),
+        Some(second) if second == rest.len() - 1 => if second > 0 {
+            parts.push(&rest[..second]);
+        },
+        Some(second) => {
+            parts.push(&rest[..second]);
+            parts.push(&rest[second + 1..]);
+        }
+    }
+    parts
+}
+
+// Bits are capped at 16, invalid numbers give -1 like in Solr, which turns them into odd masks
+fn parse_bits(bits: &str) -> i32 {
+    if bits.bytes().any(|b| !b.is_ascii_digit()) {
+        -1
+    } else if bits.is_empty() {
+        0
+    } else {
+        bits.parse::<u64>().map_or(16, |bits| bits.min(16) as i32)
+    }
+}
+
+// Shifts wrap around like Java's, for the odd masks of invalid numbers of bits
+fn bit_masks(tri_level: bool, bits: [i32; 2]) -> Vec<u32> {
+    let high = |bits: i32| if bits == 0 { 0 } else { u32::MAX.wrapping_shl((32 - bits) as u32) };
+    let first = high(bits[0]);
+    if tri_level {
+        let second = high(bits[0] + bits[1]) ^ first;
+        let third = if bits[0] + bits[1] == 32 { 0 } else { !(first | second) };
+        vec![first, second, third]
+    } else {
+        let second = if bits[0] == 32 { 0 } else { u32::MAX.wrapping_shr(bits[0] as u32) };
+        vec![first, second]
+    }
+}
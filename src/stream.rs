@@ -0,0 +1,80 @@
+use std::io::{self, Read};
+use rustc_serialize::json;
+
+use document::SolrDocument;
+use xml::{ToXml, UpdateFormat};
+
+/// Lazily encodes documents from an iterator into a JSON array or an XML add command.
+/// Only one document is held in memory at a time, so it can be used
+/// as a chunked request body of any size.
+pub struct DocumentStream<I> {
+    documents: I,
+    format: UpdateFormat,
+    buffer: Vec<u8>,
+    position: usize,
+    started: bool,
+    finished: bool
+}
+
+impl<I: Iterator<Item=SolrDocument>> DocumentStream<I> {
+    /// Creates a stream over given documents
+    pub fn new(documents: I, format: UpdateFormat) -> DocumentStream<I> {
+        DocumentStream { documents, format, buffer: Vec::new(), position: 0, started: false, finished: false }
+    }
+
+    // Refills the buffer with the next document, or the closing bracket.
+    // Returns false once everything has been encoded.
+    fn fill_buffer(&mut self) -> io::Result<bool> {
+        if self.finished {
+            return Ok(false);
+        }
+        self.buffer.clear();
+        self.position = 0;
+        match self.documents.next() {
+            Some(document) => {
+                if !self.started {
+                    self.buffer.extend_from_slice(self.opening());
+                } else if self.format == UpdateFormat::Json {
+                    self.buffer.push(b',');
+                }
+                self.started = true;
+                let encoded = match self.format {
+                    UpdateFormat::Json => try!(json::encode(&document).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))),
+                    UpdateFormat::Xml => document.to_xml()
+                };
+                self.buffer.extend_from_slice(encoded.as_bytes());
+            },
+            None => {
+                if !self.started {
+                    self.buffer.extend_from_slice(self.opening());
+                }
+                self.buffer.extend_from_slice(match self.format {
+                    UpdateFormat::Json => &b"]"[..],
+                    UpdateFormat::Xml => &b"</add>"[..]
+                });
+                self.finished = true;
+            }
+        }
+        Ok(true)
+    }
+
+    fn opening(&self) -> &'static [u8] {
+        match self.format {
+            UpdateFormat::Json => &b"["[..],
+            UpdateFormat::Xml => &b"<add>"[..]
+        }
+    }
+}
+
+impl<I: Iterator<Item=SolrDocument>> Read for DocumentStream<I> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position == self.buffer.len() && !try!(self.fill_buffer()) {
+            return Ok(0);
+        }
+        let remaining = &self.buffer[self.position..];
+        let n = ::std::cmp::min(remaining.len(), buf.len());
+        buf[..n].copy_from_slice(&remaining[..n]);
+        self.position += n;
+        Ok(n)
+    }
+}
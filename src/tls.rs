@@ -0,0 +1,60 @@
+use std::path::{Path, PathBuf};
+use openssl::error::ErrorStack;
+use openssl::ssl::{SslConnector, SslFiletype, SslMethod, SslVerifyMode};
+
+/// TLS settings for HTTPS connections to Solr.
+/// By default server certificates are verified against the system's trusted CAs.
+/// A custom CA file, a client certificate for mutual TLS, or skipping verification can be set.
+#[derive(Clone, Debug, Default)]
+pub struct SolrTlsConfig {
+    ca_file: Option<PathBuf>,
+    client_certificate: Option<(PathBuf, PathBuf)>,
+    accept_invalid_certs: bool
+}
+
+impl SolrTlsConfig {
+    /// Creates TLS settings verifying certificates against the system's trusted CAs
+    pub fn new() -> SolrTlsConfig {
+        SolrTlsConfig::default()
+    }
+
+    /// Also trusts CA certificates from a PEM file, e.g. an internal CA bundle
+    pub fn ca_file<P: AsRef<Path>>(&self, path: P) -> SolrTlsConfig {
+        let mut config = self.clone();
+        config.ca_file = Some(path.as_ref().to_path_buf());
+        config
+    }
+
+    /// Presents a client certificate, for Solr requiring mutual TLS.
+    /// Both the certificate and its private key are read from PEM files.
+    pub fn client_certificate<P: AsRef<Path>>(&self, certificate: P, key: P) -> SolrTlsConfig {
+        let mut config = self.clone();
+        config.client_certificate = Some((certificate.as_ref().to_path_buf(), key.as_ref().to_path_buf()));
+        config
+    }
+
+    /// Disables verification of server certificates and host names.
+    /// It's insecure and meant for development with self-signed certificates only.
+    pub fn accept_invalid_certs(&self, accept_invalid_certs: bool) -> SolrTlsConfig {
+        let mut config = self.clone();
+        config.accept_invalid_certs = accept_invalid_certs;
+        config
+    }
+}
+
+/// Creates an OpenSSL connector with given settings, reading certificate files
+pub fn connector(config: &SolrTlsConfig) -> Result<SslConnector, ErrorStack> {
+    let mut builder = try!(SslConnector::builder(SslMethod::tls()));
+    if let Some(ref ca_file) = config.ca_file {
+        try!(builder.set_ca_file(ca_file));
+    }
+    if let Some((ref certificate, ref key)) = config.client_certificate {
+        try!(builder.set_certificate_chain_file(certificate));
+        try!(builder.set_private_key_file(key, SslFiletype::PEM));
+        try!(builder.check_private_key());
+    }
+    if config.accept_invalid_certs {
+        builder.set_verify(SslVerifyMode::NONE);
+    }
+    Ok(builder.build())
+}
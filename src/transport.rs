@@ -0,0 +1,249 @@
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Cursor, Read};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use futures::{future, Future};
+use url::Url;
+
+/// HTTP method of a transport request
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HttpMethod {
+    Get,
+    Post
+}
+
+/// Body of a transport request
+pub enum HttpBody<'a> {
+    /// No body, used for GET requests
+    Empty,
+    /// Body held in memory
+    Text(&'a str),
+    /// Body read while being sent, with chunked transfer encoding
+    Stream(&'a mut dyn Read)
+}
+
+/// An HTTP request SolrClient asks its transport to send
+pub struct HttpRequest<'a> {
+    pub method: HttpMethod,
+    pub url: Url,
+    /// Request headers, e.g. Content-Type, as name and value pairs
+    pub headers: Vec<(String, String)>,
+    pub body: HttpBody<'a>,
+    /// Read and write timeout overriding the transport's timeouts
+    pub timeout: Option<Duration>
+}
+
+impl<'a> HttpRequest<'a> {
+    /// Creates a GET request without headers
+    pub fn get(url: Url) -> HttpRequest<'a> {
+        HttpRequest { method: HttpMethod::Get, url, headers: Vec::new(), body: HttpBody::Empty, timeout: None }
+    }
+
+    /// Creates a POST request with given content type
+    pub fn post(url: Url, content_type: &str, body: HttpBody<'a>) -> HttpRequest<'a> {
+        HttpRequest {
+            method: HttpMethod::Post,
+            url,
+            headers: vec![("Content-Type".to_string(), content_type.to_string())],
+            body,
+            timeout: None
+        }
+    }
+
+    /// Sets a header, replacing existing ones with the same name
+    pub fn set_header(&mut self, name: &str, value: &str) {
+        self.headers.retain(|header| !header.0.eq_ignore_ascii_case(name));
+        self.headers.push((name.to_string(), value.to_string()));
+    }
+
+    /// Sets the timeout overriding the transport's timeouts
+    pub fn timeout(mut self, timeout: Option<Duration>) -> HttpRequest<'a> {
+        self.timeout = timeout;
+        self
+    }
+}
+
+/// An HTTP response returned by a transport.
+/// The body is read by SolrClient as needed, so large responses can be streamed.
+pub struct HttpResponse {
+    pub status: u16,
+    /// Response headers as name and value pairs
+    pub headers: Vec<(String, String)>,
+    pub body: Box<dyn Read>
+}
+
+impl HttpResponse {
+    /// Creates a response with a body held in memory
+    pub fn from_string(status: u16, body: &str) -> HttpResponse {
+        HttpResponse { status, headers: Vec::new(), body: Box::new(Cursor::new(body.to_string().into_bytes())) }
+    }
+
+    /// Returns true for 2xx statuses
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Reads the whole body into a string
+    pub fn read_body(&mut self) -> io::Result<String> {
+        let mut body = String::new();
+        try!(self.body.read_to_string(&mut body));
+        Ok(body)
+    }
+}
+
+impl fmt::Debug for HttpResponse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HttpResponse").field("status", &self.status).field("headers", &self.headers).finish()
+    }
+}
+
+/// Sends HTTP requests on behalf of SolrClient.
+/// Implement it to swap the HTTP library or to mock Solr in tests.
+/// Network failures are reported as `io::Error`s, timeouts with kind `TimedOut` or `WouldBlock`,
+/// and connect timeouts wrapped by `connect_timeout_error`.
+pub trait Transport: Send + Sync {
+    fn execute(&self, request: HttpRequest) -> io::Result<HttpResponse>;
+}
+
+/// Future resolved with the response of an AsyncTransport
+pub type HttpFuture = Box<dyn Future<Item=HttpResponse, Error=io::Error>>;
+
+/// Sends HTTP requests on behalf of SolrAsyncClient without blocking the calling thread.
+/// The request body is copied before `send` returns, so the future doesn't borrow it.
+/// Failures are reported like by Transport.
+pub trait AsyncTransport {
+    fn send(&self, request: HttpRequest) -> HttpFuture;
+
+    /// Returns a future resolved after `duration`, used to wait between retries
+    fn delay(&self, duration: Duration) -> Box<dyn Future<Item=(), Error=io::Error>>;
+}
+
+/// Returns true if a transport error means a connect, read or write timeout elapsed
+pub fn is_timeout(err: &io::Error) -> bool {
+    // Socket read and write timeouts are reported as WouldBlock on Unix and TimedOut on Windows
+    err.kind() == io::ErrorKind::TimedOut || err.kind() == io::ErrorKind::WouldBlock
+}
+
+/// Marks an error as a timeout establishing a connection, reported as
+/// `SolrError::Timeout` with `connect` set
+pub fn connect_timeout_error(err: io::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::TimedOut, ConnectTimeout(err))
+}
+
+/// Returns true if a transport error was marked by `connect_timeout_error`
+pub fn is_connect_timeout(err: &io::Error) -> bool {
+    err.get_ref().is_some_and(|err| err.is::<ConnectTimeout>())
+}
+
+#[derive(Debug)]
+struct ConnectTimeout(io::Error);
+
+impl fmt::Display for ConnectTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Connect timed out: {}", self.0)
+    }
+}
+
+impl Error for ConnectTimeout {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+/// A request received by RecordingTransport
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordedRequest {
+    pub method: HttpMethod,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+    pub timeout: Option<Duration>
+}
+
+impl RecordedRequest {
+    /// Returns the value of a header, compared case insensitively
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|h| h.0.eq_ignore_ascii_case(name)).map(|h| &h.1[..])
+    }
+}
+
+type QueuedResponse = io::Result<(u16, String)>;
+
+static DEFAULT_RESPONSE: &str = r#"{"responseHeader":{"status":0,"QTime":0}}"#;
+
+/// In-memory transport recording requests and answering them with queued responses,
+/// for testing code using SolrClient or SolrAsyncClient without a running Solr.
+/// When no responses are queued, it answers with a successful update response.
+/// Clones share recorded requests and queued responses.
+#[derive(Clone, Default)]
+pub struct RecordingTransport {
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+    responses: Arc<Mutex<VecDeque<QueuedResponse>>>
+}
+
+impl RecordingTransport {
+    /// Creates a transport without queued responses
+    pub fn new() -> RecordingTransport {
+        RecordingTransport::default()
+    }
+
+    /// Queues a response with given status and body
+    pub fn push_response(&self, status: u16, body: &str) {
+        self.responses.lock().unwrap().push_back(Ok((status, body.to_string())));
+    }
+
+    /// Queues a network failure of given kind
+    pub fn push_error(&self, kind: io::ErrorKind) {
+        self.responses.lock().unwrap().push_back(Err(io::Error::new(kind, "RecordingTransport error")));
+    }
+
+    /// Queues a timeout establishing the connection
+    pub fn push_connect_timeout(&self) {
+        let err = io::Error::new(io::ErrorKind::TimedOut, "RecordingTransport error");
+        self.responses.lock().unwrap().push_back(Err(connect_timeout_error(err)));
+    }
+
+    /// Returns all requests received so far
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl Transport for RecordingTransport {
+    fn execute(&self, request: HttpRequest) -> io::Result<HttpResponse> {
+        let body = match request.body {
+            HttpBody::Empty => String::new(),
+            HttpBody::Text(text) => text.to_string(),
+            HttpBody::Stream(reader) => {
+                let mut body = String::new();
+                try!(reader.read_to_string(&mut body));
+                body
+            }
+        };
+        self.requests.lock().unwrap().push(RecordedRequest {
+            method: request.method,
+            url: request.url.serialize(),
+            headers: request.headers,
+            body,
+            timeout: request.timeout
+        });
+        match self.responses.lock().unwrap().pop_front() {
+            Some(Ok((status, body))) => Ok(HttpResponse::from_string(status, &body)),
+            Some(Err(err)) => Err(err),
+            None => Ok(HttpResponse::from_string(200, DEFAULT_RESPONSE))
+        }
+    }
+}
+
+// Answers right away, so futures can be waited for without an event loop
+impl AsyncTransport for RecordingTransport {
+    fn send(&self, request: HttpRequest) -> HttpFuture {
+        Box::new(future::result(self.execute(request)))
+    }
+
+    fn delay(&self, _: Duration) -> Box<dyn Future<Item=(), Error=io::Error>> {
+        Box::new(future::ok(()))
+    }
+}
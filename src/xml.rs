@@ -0,0 +1,48 @@
+use document::SolrDocument;
+
+/// Serialization to Solr XML update format
+pub trait ToXml {
+    fn to_xml(&self) -> String;
+}
+
+/// Format of update request bodies sent by SolrClient
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UpdateFormat {
+    /// JSON update format (default)
+    Json,
+    /// XML update format, `<add><doc><field name="..">..</field></doc></add>`
+    Xml
+}
+
+/// Escapes text for use in XML element content and attribute values
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c)
+        }
+    }
+    escaped
+}
+
+impl<T: ToXml + ?Sized> ToXml for &T {
+    fn to_xml(&self) -> String {
+        (**self).to_xml()
+    }
+}
+
+impl ToXml for [&SolrDocument] {
+    fn to_xml(&self) -> String {
+        let mut xml = String::from("<add>");
+        for document in self.iter() {
+            xml.push_str(&document.to_xml());
+        }
+        xml.push_str("</add>");
+        xml
+    }
+}
@@ -0,0 +1,162 @@
+extern crate futures;
+extern crate heliotrope;
+extern crate tokio_core;
+extern crate url;
+
+use std::io;
+use std::sync::{Arc, Barrier};
+use std::thread;
+use std::time::Duration;
+use futures::Future;
+use futures::future::join_all;
+use tokio_core::reactor::Core;
+use url::Url;
+use heliotrope::{HyperAsyncTransport, RecordingTransport, SolrAsyncClient, SolrClient, SolrDocument, SolrError,
+                 SolrProxyConfig, SolrQuery, SolrRetryPolicy, SolrUpdateBatch};
+
+mod common;
+use common::StubSolr;
+
+static QUERY_RESPONSE: &str = r#"{"responseHeader":{"status":0,"QTime":1},"response":{"numFound":1,"start":0,"docs":[{"id":"1"}]}}"#;
+static PING_RESPONSE: &str = r#"{"responseHeader":{"status":0,"QTime":1},"status":"OK"}"#;
+
+fn client(transport: &RecordingTransport) -> SolrAsyncClient {
+    let url = Url::parse("http://localhost:8983/solr/test/").unwrap();
+    SolrAsyncClient::with_transport(SolrClient::new(&url), transport.clone())
+}
+
+#[test]
+fn async_requests_are_chained() {
+    let transport = RecordingTransport::new();
+    let solr = client(&transport);
+    let mut document = SolrDocument::new();
+    document.add_field("id", "1");
+    transport.push_response(200, r#"{"responseHeader":{"status":0,"QTime":2}}"#);
+    transport.push_response(200, r#"{"responseHeader":{"status":0,"QTime":3}}"#);
+    transport.push_response(200, QUERY_RESPONSE);
+
+    let total = solr.add(&document)
+        .and_then(|_| solr.commit())
+        .and_then(|_| solr.query(&SolrQuery::new("id:1")))
+        .map(|response| response.total)
+        .wait().ok().unwrap();
+    assert_eq!(total, 1);
+
+    let requests = transport.requests();
+    let urls: Vec<&str> = requests.iter().map(|request| &request.url[..]).collect();
+    assert_eq!(urls, vec!["http://localhost:8983/solr/test/update?wt=json",
+                          "http://localhost:8983/solr/test/update?wt=json&commit=true",
+                          "http://localhost:8983/solr/test/select?wt=json&q=id%3A1"]);
+    assert_eq!(requests[0].body, r#"[{"id":"1"}]"#);
+}
+
+#[test]
+fn async_client_sends_the_same_updates_and_gets_as_the_blocking_one() {
+    let transport = RecordingTransport::new();
+    let solr = client(&transport);
+    let mut document = SolrDocument::new();
+    document.add_field("id", "1");
+    let mut batch = SolrUpdateBatch::new();
+    batch.delete_by_id("2");
+    batch.commit();
+    transport.push_response(200, r#"{"responseHeader":{"status":0,"QTime":1}}"#);
+    transport.push_response(200, r#"{"responseHeader":{"status":0,"QTime":1}}"#);
+    transport.push_response(200, QUERY_RESPONSE);
+
+    assert!(solr.add_and_commit(&document).wait().is_ok());
+    assert!(solr.update_batch(&batch).wait().is_ok());
+    let found = solr.get_by_id("1").wait().ok().unwrap().unwrap();
+    assert_eq!(found.get_str("id"), Some("1"));
+
+    let requests = transport.requests();
+    assert_eq!(requests[0].url, "http://localhost:8983/solr/test/update?wt=json&commit=true");
+    assert_eq!(requests[1].body, r#"{"delete":[{"id":"2"}],"commit":{}}"#);
+    assert_eq!(requests[2].url, "http://localhost:8983/solr/test/get?wt=json&omitHeader=false&ids=1");
+}
+
+#[test]
+fn async_errors_are_returned_by_futures() {
+    let transport = RecordingTransport::new();
+    transport.push_response(400, r#"{"responseHeader":{"status":400,"QTime":1},"error":{"msg":"undefined field","code":400}}"#);
+    let error = client(&transport).query(&SolrQuery::new("foo:bar")).wait().err().unwrap();
+    assert_eq!(error.status(), Some(400));
+}
+
+#[test]
+fn async_requests_are_retried_with_the_client_policy() {
+    let transport = RecordingTransport::new();
+    let url = Url::parse("http://localhost:8983/solr/test/").unwrap();
+    let blocking = SolrClient::builder(&url).basic_auth("solr", "secret")
+        .retry_policy(SolrRetryPolicy::new().max_attempts(3))
+        .build();
+    let solr = SolrAsyncClient::with_transport(blocking, transport.clone());
+    transport.push_response(503, "Service Unavailable");
+    transport.push_response(200, PING_RESPONSE);
+    assert!(solr.ping().wait().is_ok());
+
+    transport.push_response(503, "Service Unavailable");
+    let error = solr.commit().wait().err().unwrap();
+    assert_eq!(error.status(), Some(503));
+    assert_eq!(error.attempts(), 1);
+
+    let requests = transport.requests();
+    assert_eq!(requests.len(), 3);
+    assert!(requests.iter().all(|request| request.header("Authorization").is_some()));
+}
+
+#[test]
+fn requests_are_sent_concurrently_without_blocking_the_reactor() {
+    // Solr answers only once three requests are waiting, so they must be in flight together
+    let barrier = Arc::new(Barrier::new(3));
+    let stub = StubSolr::with_handler(move |_| {
+        barrier.wait();
+        (200, PING_RESPONSE.to_string())
+    });
+    let mut core = Core::new().unwrap();
+    let transport = HyperAsyncTransport::new(&core.handle()).timeout(Duration::from_secs(5));
+    let solr = SolrAsyncClient::with_transport(SolrClient::new(&stub.url), transport);
+    let pings = (0..3).map(|_| solr.ping()).collect::<Vec<_>>();
+    assert_eq!(core.run(join_all(pings)).unwrap().len(), 3);
+    assert_eq!(stub.requests().len(), 3);
+}
+
+#[test]
+fn requests_not_answered_in_time_fail_with_timeout() {
+    let stub = StubSolr::with_handler(|_| {
+        thread::sleep(Duration::from_millis(500));
+        (200, PING_RESPONSE.to_string())
+    });
+    let mut core = Core::new().unwrap();
+    let transport = HyperAsyncTransport::new(&core.handle()).timeout(Duration::from_millis(50));
+    let solr = SolrAsyncClient::with_transport(SolrClient::new(&stub.url), transport);
+    assert!(matches!(core.run(solr.ping()), Err(SolrError::Timeout { .. })));
+}
+
+#[test]
+fn builder_settings_apply_to_the_async_transport() {
+    let stub = StubSolr::new();
+    let mut core = Core::new().unwrap();
+    let builder = SolrClient::builder(&stub.url).request_compression(10).read_timeout(Duration::from_secs(5));
+    let solr = SolrAsyncClient::from_builder(&builder, &core.handle()).unwrap();
+    let mut document = SolrDocument::new();
+    document.add_field("id", "1234567890");
+    assert!(core.run(solr.add(&document)).is_ok());
+
+    let requests = stub.requests();
+    assert_eq!(requests[0].header("Accept-Encoding"), Some("gzip, deflate"));
+    assert_eq!(requests[0].header("Content-Encoding"), Some("gzip"));
+    assert_eq!(requests[0].body, r#"[{"id":"1234567890"}]"#);
+}
+
+#[test]
+fn unsupported_transport_settings_are_rejected() {
+    let core = Core::new().unwrap();
+    let url = Url::parse("http://localhost:8983/solr/test/").unwrap();
+    let proxy = SolrProxyConfig::new(&Url::parse("http://proxy:3128/").unwrap());
+    let error = SolrAsyncClient::from_builder(&SolrClient::builder(&url).proxy(proxy), &core.handle()).err().unwrap();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+
+    let url = Url::parse("https://localhost:8983/solr/test/").unwrap();
+    let error = SolrAsyncClient::new(&url, &core.handle()).ping().wait().err().unwrap();
+    assert!(error.to_string().contains("only http URLs"));
+}
@@ -0,0 +1,111 @@
+extern crate rustc_serialize;
+extern crate url;
+extern crate heliotrope;
+
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use rustc_serialize::json::Json;
+use heliotrope::{BulkIndexer, BulkIndexerOptions, SolrClient, SolrDocument};
+
+mod common;
+use common::{StubSolr, unreachable_url};
+
+// Number of documents in every update received by the stub
+fn batch_sizes(solr: &StubSolr) -> Vec<usize> {
+    let mut sizes: Vec<usize> = solr.requests().iter()
+        .map(|r| Json::from_str(&r.body).unwrap().as_array().unwrap().len())
+        .collect();
+    sizes.sort();
+    sizes
+}
+
+fn document(id: usize) -> SolrDocument {
+    let mut document = SolrDocument::new();
+    document.add_field("id", &id.to_string());
+    document
+}
+
+#[test]
+fn bulk_indexer_flushes_by_document_count() {
+    let solr = StubSolr::new();
+    let client = SolrClient::new(&solr.url);
+    let outcomes = Arc::new(Mutex::new(Vec::new()));
+    let recorded = outcomes.clone();
+    let options = BulkIndexerOptions::new()
+        .max_documents(10)
+        .workers(2)
+        .flush_interval(None)
+        .on_batch(move |outcome| recorded.lock().unwrap().push((outcome.documents, outcome.result.is_ok())));
+    let indexer = BulkIndexer::new(client, options);
+    for id in 0..25 {
+        indexer.add(document(id));
+    }
+    let totals = indexer.finish();
+    assert_eq!(totals.batches, 3);
+    assert_eq!(totals.indexed, 25);
+    assert_eq!(totals.failed, 0);
+    assert_eq!(batch_sizes(&solr), vec!(5, 10, 10));
+    assert_eq!(outcomes.lock().unwrap().len(), 3);
+    assert!(outcomes.lock().unwrap().iter().all(|&(_, ok)| ok));
+}
+
+#[test]
+fn bulk_indexer_flushes_by_size() {
+    let solr = StubSolr::new();
+    let client = SolrClient::new(&solr.url);
+    // Every document takes a bit over 250 bytes, so 4 of them fill a batch
+    let options = BulkIndexerOptions::new().max_bytes(1000).flush_interval(None);
+    let indexer = BulkIndexer::new(client, options);
+    let text: String = "x".repeat(250);
+    for id in 0..10 {
+        let mut document = document(id);
+        document.add_field("text", &text);
+        indexer.add(document);
+    }
+    let totals = indexer.finish();
+    assert_eq!(totals.batches, 3);
+    assert_eq!(totals.indexed, 10);
+    assert_eq!(batch_sizes(&solr), vec!(2, 4, 4));
+}
+
+#[test]
+fn bulk_indexer_flushes_by_time() {
+    let solr = StubSolr::new();
+    let client = SolrClient::new(&solr.url);
+    let options = BulkIndexerOptions::new().flush_interval(Some(Duration::from_millis(50)));
+    let indexer = BulkIndexer::new(client, options);
+    indexer.add(document(1));
+    indexer.add(document(2));
+    thread::sleep(Duration::from_millis(500));
+    assert_eq!(batch_sizes(&solr), vec!(2));
+    let totals = indexer.finish();
+    assert_eq!(totals.batches, 1);
+    assert_eq!(totals.indexed, 2);
+}
+
+#[test]
+fn bulk_indexer_reports_failed_batches_after_retries() {
+    let client = SolrClient::new(&unreachable_url());
+    let options = BulkIndexerOptions::new().max_retries(2).retry_delay(Duration::from_millis(1));
+    let indexer = BulkIndexer::new(client, options);
+    indexer.add(document(1));
+    let totals = indexer.finish();
+    assert_eq!(totals.batches, 1);
+    assert_eq!(totals.failed_batches, 1);
+    assert_eq!(totals.failed, 1);
+    assert_eq!(totals.retries, 2);
+}
+
+#[test]
+fn batches_rejected_by_solr_are_not_retried() {
+    let solr = StubSolr::with_handler(|_| (400, r#"{"error":{"msg":"Document is missing mandatory uniqueKey field","code":400}}"#.to_string()));
+    let client = SolrClient::new(&solr.url);
+    let options = BulkIndexerOptions::new().max_retries(2).retry_delay(Duration::from_millis(1));
+    let indexer = BulkIndexer::new(client, options);
+    indexer.add(document(1));
+    let totals = indexer.finish();
+    assert_eq!(totals.failed_batches, 1);
+    assert_eq!(totals.retries, 0);
+    assert_eq!(solr.requests().len(), 1);
+}
@@ -0,0 +1,254 @@
+extern crate heliotrope;
+extern crate rustc_serialize;
+extern crate url;
+
+use std::sync::{Arc, Mutex};
+use rustc_serialize::json::Json;
+use url::Url;
+use heliotrope::{SolrCloudClient, SolrClusterState, SolrDocument, SolrError, SolrQuery};
+
+mod common;
+use common::{RecordedRequest, StubSolr, UPDATE_RESPONSE, unreachable_url};
+
+static QUERY_RESPONSE: &str = r#"{"responseHeader":{"status":0,"QTime":1},"response":{"numFound":0,"start":0,"docs":[]}}"#;
+
+/// Base URL and node name of a node, e.g. http://127.0.0.1:8983/solr and 127.0.0.1:8983_solr
+fn node(url: &Url) -> (String, String) {
+    let host = format!("{}:{}", url.serialize_host().unwrap(), url.port().unwrap());
+    (format!("http://{}/solr", host), format!("{}_solr", host))
+}
+
+fn replica(name: &str, core: &str, node: &(String, String), leader: bool) -> String {
+    format!(r#""{}":{{"core":"{}","base_url":"{}","node_name":"{}","state":"active","type":"NRT","leader":"{}"}}"#,
+            name, core, node.0, node.1, leader)
+}
+
+/// CLUSTERSTATUS response of collection `test` with two shards, each with a replica on both nodes.
+/// Documents with negative hashes, like id 1, belong to shard1 and the others, like id 2, to shard2.
+fn cluster_status(first: &Url, second: &Url, live: &[&Url]) -> String {
+    let (first, second) = (node(first), node(second));
+    let live: Vec<String> = live.iter().map(|url| format!(r#""{}""#, node(url).1)).collect();
+    format!(r#"{{"responseHeader":{{"status":0,"QTime":3}},"cluster":{{"collections":{{"test":{{
+        "shards":{{
+            "shard1":{{"range":"80000000-ffffffff","state":"active","replicas":{{{},{}}}}},
+            "shard2":{{"range":"0-7fffffff","state":"active","replicas":{{{},{}}}}}}},
+        "router":{{"name":"compositeId"}}}}}},"live_nodes":[{}]}}}}"#,
+        replica("core_node1", "test_shard1_replica_n1", &first, true),
+        replica("core_node3", "test_shard1_replica_n3", &second, false),
+        replica("core_node2", "test_shard2_replica_n2", &second, true),
+        replica("core_node4", "test_shard2_replica_n4", &first, false),
+        live.join(","))
+}
+
+/// A node answering with cluster state set later
+fn solr_node(state: &Arc<Mutex<Vec<String>>>) -> StubSolr {
+    let state = state.clone();
+    StubSolr::with_handler(move |request: &RecordedRequest| {
+        if request.path.contains("/admin/collections") {
+            let mut state = state.lock().unwrap();
+            let current = state[0].clone();
+            if state.len() > 1 {
+                state.remove(0);
+            }
+            (200, current)
+        } else if request.path.contains("/select") {
+            (200, QUERY_RESPONSE.to_string())
+        } else {
+            (200, UPDATE_RESPONSE.to_string())
+        }
+    })
+}
+
+fn document(id: &str) -> SolrDocument {
+    let mut document = SolrDocument::new();
+    document.add_field("id", id);
+    document
+}
+
+fn paths(solr: &StubSolr) -> Vec<String> {
+    solr.requests().into_iter().map(|request| request.path).collect()
+}
+
+#[test]
+fn cluster_state_is_read_from_collections_api() {
+    let state = Arc::new(Mutex::new(Vec::new()));
+    let (first, second) = (solr_node(&state), solr_node(&state));
+    state.lock().unwrap().push(cluster_status(&first.url, &second.url, &[&first.url, &second.url]));
+    let client = SolrCloudClient::new(&first.url.join("..").unwrap(), "test");
+
+    let cluster = client.cluster_state().ok().unwrap();
+    assert_eq!(cluster.router, "compositeId");
+    assert_eq!(cluster.live_nodes.len(), 2);
+    let shard1 = cluster.shards.iter().find(|shard| shard.name == "shard1").unwrap();
+    assert_eq!(shard1.range, Some((i32::MIN, -1)));
+    assert_eq!(shard1.leader().unwrap().core, "test_shard1_replica_n1");
+    assert_eq!(paths(&first), vec!["/solr/admin/collections?wt=json&action=CLUSTERSTATUS&collection=test"]);
+}
+
+#[test]
+fn documents_are_sent_to_shard_leaders() {
+    let state = Arc::new(Mutex::new(Vec::new()));
+    let (first, second) = (solr_node(&state), solr_node(&state));
+    state.lock().unwrap().push(cluster_status(&first.url, &second.url, &[&first.url, &second.url]));
+    let client = SolrCloudClient::new(&first.url.join("..").unwrap(), "test");
+
+    assert!(client.add_many(&[&document("1"), &document("2"), &document("3")]).is_ok());
+    let first_requests = first.requests();
+    assert_eq!(first_requests[1].path, "/solr/test_shard1_replica_n1/update?wt=json");
+    assert_eq!(first_requests[1].body, r#"[{"id":"1"}]"#);
+    let second_requests = second.requests();
+    assert_eq!(second_requests[0].path, "/solr/test_shard2_replica_n2/update?wt=json");
+    assert_eq!(second_requests[0].body, r#"[{"id":"2"},{"id":"3"}]"#);
+
+    assert!(client.delete_by_id("1").is_ok());
+    assert_eq!(first.requests()[2].path, "/solr/test_shard1_replica_n1/update?wt=json&commit=true");
+}
+
+#[test]
+fn queries_go_to_replicas_on_live_nodes() {
+    let state = Arc::new(Mutex::new(Vec::new()));
+    let (first, second) = (solr_node(&state), solr_node(&state));
+    state.lock().unwrap().push(cluster_status(&first.url, &second.url, &[&second.url]));
+    let client = SolrCloudClient::new(&first.url.join("..").unwrap(), "test");
+
+    for _ in 0..4 {
+        assert!(client.query(&SolrQuery::new("*:*")).is_ok());
+    }
+    assert_eq!(first.requests().len(), 1);
+    assert_eq!(second.requests().len(), 4);
+    assert!(paths(&second).iter().all(|path| path.starts_with("/solr/test/select?")));
+}
+
+#[test]
+fn stale_state_is_refreshed() {
+    let state = Arc::new(Mutex::new(Vec::new()));
+    let (first, second) = (solr_node(&state), solr_node(&state));
+    let gone = unreachable_url();
+    state.lock().unwrap().push(cluster_status(&first.url, &gone, &[&first.url, &gone]));
+    state.lock().unwrap().push(cluster_status(&first.url, &second.url, &[&first.url, &second.url]));
+    let client = SolrCloudClient::new(&first.url.join("..").unwrap(), "test");
+
+    assert!(client.add(&document("2")).is_ok());
+    assert_eq!(paths(&second), vec!["/solr/test_shard2_replica_n2/update?wt=json"]);
+    let collections_requests = paths(&first).iter().filter(|path| path.contains("admin/collections")).count();
+    assert_eq!(collections_requests, 2);
+}
+
+#[test]
+fn replicas_without_base_url_are_located_by_node_name_or_left_out() {
+    let state = Arc::new(Mutex::new(Vec::new()));
+    let node = solr_node(&state);
+    let (_, node_name) = self::node(&node.url);
+    state.lock().unwrap().push(format!(r#"{{"cluster":{{"collections":{{"test":{{"shards":{{
+        "shard1":{{"range":"80000000-7fffffff","state":"active","replicas":{{
+            "core_node1":{{"core":"test_shard1_replica_n1","node_name":"{}","state":"active","leader":"true"}},
+            "core_node2":{{"core":"test_shard1_replica_n2","node_name":"","state":"active"}}}}}}}},
+        "router":{{"name":"compositeId"}}}}}},"live_nodes":["{}"]}}}}"#, node_name, node_name));
+    let client = SolrCloudClient::new(&node.url.join("..").unwrap(), "test");
+
+    let cluster = client.cluster_state().ok().unwrap();
+    let replicas = &cluster.shards[0].replicas;
+    assert_eq!(replicas.len(), 1);
+    assert_eq!(replicas[0].base_url, self::node(&node.url).0);
+    assert!(client.query(&SolrQuery::new("*:*")).is_ok());
+    assert!(client.add(&document("1")).is_ok());
+    assert_eq!(paths(&node)[2], "/solr/test_shard1_replica_n1/update?wt=json");
+}
+
+#[test]
+fn only_groups_failing_on_stale_state_are_sent_again() {
+    let state = Arc::new(Mutex::new(Vec::new()));
+    let (first, second) = (solr_node(&state), solr_node(&state));
+    let gone = unreachable_url();
+    state.lock().unwrap().push(cluster_status(&first.url, &gone, &[&first.url, &gone]));
+    state.lock().unwrap().push(cluster_status(&first.url, &second.url, &[&first.url, &second.url]));
+    let client = SolrCloudClient::new(&first.url.join("..").unwrap(), "test");
+
+    assert!(client.add_many(&[&document("1"), &document("2"), &document("3")]).is_ok());
+    let updates: Vec<String> = paths(&first).into_iter().filter(|path| path.contains("/update")).collect();
+    assert_eq!(updates, vec!["/solr/test_shard1_replica_n1/update?wt=json"]);
+    assert_eq!(second.requests()[0].body, r#"[{"id":"2"},{"id":"3"}]"#);
+}
+
+#[test]
+fn failures_of_some_shards_are_reported_after_sending_all_groups() {
+    let state = Arc::new(Mutex::new(Vec::new()));
+    let first = solr_node(&state);
+    let cluster = state.clone();
+    let second = StubSolr::with_handler(move |request: &RecordedRequest| {
+        if request.path.contains("/admin/collections") {
+            (200, cluster.lock().unwrap()[0].clone())
+        } else {
+            (400, r#"{"error":{"msg":"Document is missing mandatory uniqueKey field","code":400}}"#.to_string())
+        }
+    });
+    state.lock().unwrap().push(cluster_status(&first.url, &second.url, &[&first.url, &second.url]));
+    let client = SolrCloudClient::new(&first.url.join("..").unwrap(), "test");
+
+    match client.add_many(&[&document("2"), &document("1")]).err().unwrap() {
+        SolrError::Partial { failures } => {
+            assert_eq!(failures.len(), 1);
+            assert!(failures[0].0.ends_with("/solr/test_shard2_replica_n2/"));
+            assert_eq!(failures[0].1.status(), Some(400));
+        },
+        err => panic!("unexpected error {:?}", err)
+    }
+    assert_eq!(first.requests()[1].body, r#"[{"id":"1"}]"#);
+    assert_eq!(second.requests().len(), 1);
+
+    assert_eq!(client.delete_by_id("2").err().unwrap().status(), Some(400));
+}
+
+#[test]
+fn update_failing_on_every_shard_is_not_partial() {
+    let state: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+    let cluster = state.clone();
+    let handler = move |request: &RecordedRequest| {
+        if request.path.contains("/admin/collections") {
+            (200, cluster.lock().unwrap()[0].clone())
+        } else {
+            (400, r#"{"error":{"msg":"Document is missing mandatory uniqueKey field","code":400}}"#.to_string())
+        }
+    };
+    let (first, second) = (StubSolr::with_handler(handler.clone()), StubSolr::with_handler(handler));
+    state.lock().unwrap().push(cluster_status(&first.url, &second.url, &[&first.url, &second.url]));
+    let client = SolrCloudClient::new(&first.url.join("..").unwrap(), "test");
+
+    match client.add_many(&[&document("2"), &document("1")]).err().unwrap() {
+        SolrError::Solr { status: 400, .. } => {},
+        err => panic!("unexpected error {:?}", err)
+    }
+    assert_eq!(second.requests().len(), 1);
+}
+
+#[test]
+fn documents_which_cant_be_routed_dont_stop_other_shards() {
+    let state = Arc::new(Mutex::new(Vec::new()));
+    let first = solr_node(&state);
+    let gone = unreachable_url();
+    // The leader of shard2, holding id 2, is down
+    state.lock().unwrap().push(cluster_status(&first.url, &gone, &[&first.url]));
+    let client = SolrCloudClient::new(&first.url.join("..").unwrap(), "test");
+
+    match client.add_many(&[&document("1"), &document("2")]).err().unwrap() {
+        SolrError::Partial { failures } => {
+            assert_eq!(failures.len(), 1);
+            assert_eq!(failures[0].0, "2");
+            assert!(matches!(failures[0].1, SolrError::Unavailable(_)));
+        },
+        err => panic!("unexpected error {:?}", err)
+    }
+    let updates: Vec<RecordedRequest> = first.requests().into_iter().filter(|request| request.path.contains("/update")).collect();
+    assert_eq!(updates.len(), 1);
+    assert_eq!(updates[0].body, r#"[{"id":"1"}]"#);
+}
+
+#[test]
+fn replicas_located_by_node_name_use_the_cluster_url_scheme() {
+    let json = Json::from_str(r#"{"cluster":{"properties":{"urlScheme":"https"},"collections":{"test":{"shards":{
+        "shard1":{"range":"80000000-7fffffff","state":"active","replicas":{
+            "core_node1":{"core":"test_shard1_replica_n1","node_name":"solr1:8984_solr","state":"active","leader":"true"}}}},
+        "router":{"name":"compositeId"}}},"live_nodes":["solr1:8984_solr"]}}"#).unwrap();
+    let state = SolrClusterState::from_json(&json, "test").ok().unwrap();
+    assert_eq!(state.shards[0].replicas[0].base_url, "https://solr1:8984/solr");
+}
@@ -0,0 +1,141 @@
+// Helpers shared by integration tests that need an HTTP stand-in for Solr
+#![allow(dead_code)]
+
+extern crate flate2;
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use url::Url;
+use self::flate2::read::GzDecoder;
+
+pub const UPDATE_RESPONSE: &str = r#"{"responseHeader":{"status":0,"QTime":1}}"#;
+
+/// A request received by StubSolr
+#[derive(Clone, Debug)]
+pub struct RecordedRequest {
+    /// Sequence number of the TCP connection the request came on
+    pub connection: usize,
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String
+}
+
+impl RecordedRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, v)| &v[..])
+    }
+}
+
+pub type Handler = Arc<dyn Fn(&RecordedRequest) -> (u16, String) + Send + Sync>;
+
+/// Minimal HTTP/1.1 server recording requests and answering them with a handler
+pub struct StubSolr {
+    pub url: Url,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>
+}
+
+impl StubSolr {
+    /// Starts a server answering every request with a successful update response
+    pub fn new() -> StubSolr {
+        StubSolr::with_handler(|_| (200, UPDATE_RESPONSE.to_string()))
+    }
+
+    pub fn with_handler<F>(handler: F) -> StubSolr
+        where F: Fn(&RecordedRequest) -> (u16, String) + Send + Sync + 'static {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        let handler: Handler = Arc::new(handler);
+        thread::spawn(move || {
+            for (connection, stream) in listener.incoming().enumerate() {
+                let recorded = recorded.clone();
+                let handler = handler.clone();
+                thread::spawn(move || serve(connection, stream.unwrap(), recorded, handler));
+            }
+        });
+        StubSolr {
+            url: Url::parse(&format!("http://127.0.0.1:{}/solr/test/", port)).unwrap(),
+            requests
+        }
+    }
+
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+/// URL of a port nothing listens on
+pub fn unreachable_url() -> Url {
+    let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    Url::parse(&format!("http://127.0.0.1:{}/solr/test/", port)).unwrap()
+}
+
+/// Answers HTTP requests read from a connection until it's closed
+pub fn serve<S: Read + Write>(connection: usize, stream: S, requests: Arc<Mutex<Vec<RecordedRequest>>>, handler: Handler) {
+    let mut reader = BufReader::new(stream);
+    loop {
+        let mut request_line = String::new();
+        if reader.read_line(&mut request_line).unwrap_or(0) == 0 {
+            return;
+        }
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next().unwrap_or("").to_string();
+        let path = parts.next().unwrap_or("").to_string();
+        let mut headers = Vec::new();
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                return;
+            }
+            if line == "\r\n" {
+                break;
+            }
+            let mut header = line.splitn(2, ':');
+            let name = header.next().unwrap().trim().to_string();
+            let value = header.next().unwrap_or("").trim().to_string();
+            headers.push((name, value));
+        }
+        let mut request = RecordedRequest { connection, method, path, headers, body: String::new() };
+        let body = if request.header("Transfer-Encoding").is_some_and(|v| v.eq_ignore_ascii_case("chunked")) {
+            read_chunked(&mut reader)
+        } else {
+            let length = request.header("Content-Length").map_or(0, |v| v.parse().unwrap());
+            let mut body = vec![0u8; length];
+            reader.read_exact(&mut body).unwrap();
+            body
+        };
+        let body = if request.header("Content-Encoding") == Some("gzip") {
+            let mut decoded = Vec::new();
+            GzDecoder::new(&body[..]).read_to_end(&mut decoded).unwrap();
+            decoded
+        } else {
+            body
+        };
+        request.body = String::from_utf8(body).unwrap();
+        requests.lock().unwrap().push(request.clone());
+        let (status, response) = handler(&request);
+        if write!(reader.get_mut(), "HTTP/1.1 {} Stub\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                  status, response.len(), response).is_err() {
+            return;
+        }
+    }
+}
+
+fn read_chunked<R: BufRead>(reader: &mut R) -> Vec<u8> {
+    let mut body = Vec::new();
+    loop {
+        let mut size_line = String::new();
+        reader.read_line(&mut size_line).unwrap();
+        let size = usize::from_str_radix(size_line.trim(), 16).unwrap();
+        let mut chunk = vec![0u8; size + 2];
+        reader.read_exact(&mut chunk).unwrap();
+        if size == 0 {
+            return body;
+        }
+        body.extend_from_slice(&chunk[..size]);
+    }
+}
@@ -0,0 +1,93 @@
+extern crate flate2;
+extern crate heliotrope;
+extern crate tokio_core;
+extern crate url;
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use flate2::Compression;
+use flate2::write::{GzEncoder, ZlibEncoder};
+use tokio_core::reactor::Core;
+use url::Url;
+use heliotrope::{SolrAsyncClient, SolrClient, SolrDocument, SolrQuery};
+
+mod common;
+use common::StubSolr;
+
+static QUERY_RESPONSE: &str = r#"{"responseHeader":{"status":0,"QTime":1},"response":{"numFound":1,"start":0,"docs":[{"id":"1"}]}}"#;
+
+/// Starts a server answering every request with an encoded query response, recording request heads
+fn encoded_solr(encoding: &'static str, body: Vec<u8>) -> (Url, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let heads = Arc::new(Mutex::new(Vec::new()));
+    let recorded = heads.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut reader = BufReader::new(stream.unwrap());
+            let mut head = String::new();
+            while !head.ends_with("\r\n\r\n") && reader.read_line(&mut head).unwrap() > 0 {}
+            recorded.lock().unwrap().push(head);
+            let stream = reader.get_mut();
+            write!(stream, "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Encoding: {}\r\n\
+                            Content-Length: {}\r\nConnection: close\r\n\r\n", encoding, body.len()).unwrap();
+            stream.write_all(&body).unwrap();
+        }
+    });
+    (Url::parse(&format!("http://127.0.0.1:{}/solr/test/", port)).unwrap(), heads)
+}
+
+#[test]
+fn gzip_response_is_decompressed() {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(QUERY_RESPONSE.as_bytes()).unwrap();
+    let (url, heads) = encoded_solr("gzip", encoder.finish().unwrap());
+    let response = SolrClient::new(&url).query(&SolrQuery::new("*:*")).ok().unwrap();
+    assert_eq!(response.total, 1);
+    assert!(heads.lock().unwrap()[0].contains("Accept-Encoding: gzip, deflate\r\n"));
+}
+
+#[test]
+fn gzip_response_is_decompressed_by_the_async_client() {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(QUERY_RESPONSE.as_bytes()).unwrap();
+    let (url, _) = encoded_solr("gzip", encoder.finish().unwrap());
+    let mut core = Core::new().unwrap();
+    let solr = SolrAsyncClient::new(&url, &core.handle());
+    assert_eq!(core.run(solr.query(&SolrQuery::new("*:*"))).ok().unwrap().total, 1);
+}
+
+#[test]
+fn deflate_response_is_decompressed() {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(QUERY_RESPONSE.as_bytes()).unwrap();
+    let (url, _) = encoded_solr("deflate", encoder.finish().unwrap());
+    assert_eq!(SolrClient::new(&url).query(&SolrQuery::new("*:*")).ok().unwrap().total, 1);
+}
+
+#[test]
+fn response_compression_can_be_disabled() {
+    let solr = StubSolr::new();
+    assert!(SolrClient::builder(&solr.url).response_compression(false).build().commit().is_ok());
+    assert!(solr.requests()[0].header("Accept-Encoding").is_none());
+}
+
+#[test]
+fn large_update_bodies_are_compressed() {
+    let solr = StubSolr::new();
+    let client = SolrClient::builder(&solr.url).request_compression(100).build();
+    let documents: Vec<SolrDocument> = (0..10).map(|i| {
+        let mut document = SolrDocument::new();
+        document.add_field("id", &i.to_string());
+        document
+    }).collect();
+    assert!(client.add_many(&documents.iter().collect::<Vec<_>>()).is_ok());
+    assert!(client.add(&documents[0]).is_ok());
+
+    let requests = solr.requests();
+    assert_eq!(requests[0].header("Content-Encoding"), Some("gzip"));
+    assert!(requests[0].body.contains(r#"{"id":"9"}"#));
+    assert!(requests[1].header("Content-Encoding").is_none());
+}
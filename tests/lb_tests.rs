@@ -0,0 +1,108 @@
+extern crate heliotrope;
+extern crate url;
+
+use std::io;
+use std::thread;
+use std::time::Duration;
+use url::Url;
+use heliotrope::{RecordingTransport, SolrClient, SolrError, SolrLbClient, SolrQuery};
+
+static QUERY_RESPONSE: &str = r#"{"responseHeader":{"status":0,"QTime":1},"response":{"numFound":0,"start":0,"docs":[]}}"#;
+static PING_RESPONSE: &str = r#"{"responseHeader":{"status":0,"QTime":1},"status":"OK"}"#;
+
+fn node(name: &str, transport: &RecordingTransport) -> SolrClient {
+    let url = Url::parse(&format!("http://{}:8983/solr/test/", name)).unwrap();
+    SolrClient::builder(&url).transport(transport.clone()).build()
+}
+
+fn lb_client(transports: &[RecordingTransport], recheck_interval: Duration) -> SolrLbClient {
+    let clients = transports.iter().enumerate().map(|(i, transport)| node(&format!("solr{}", i), transport)).collect();
+    SolrLbClient::with_clients(clients, recheck_interval)
+}
+
+#[test]
+fn queries_are_sent_round_robin() {
+    let transports = vec![RecordingTransport::new(), RecordingTransport::new()];
+    let solr = lb_client(&transports, Duration::from_secs(60));
+    for _ in 0..4 {
+        transports[0].push_response(200, QUERY_RESPONSE);
+        transports[1].push_response(200, QUERY_RESPONSE);
+        assert!(solr.query(&SolrQuery::new("*:*")).is_ok());
+    }
+    assert_eq!(transports[0].requests().len(), 2);
+    assert_eq!(transports[1].requests().len(), 2);
+}
+
+#[test]
+fn unreachable_node_is_marked_dead_and_skipped() {
+    let transports = vec![RecordingTransport::new(), RecordingTransport::new()];
+    let solr = lb_client(&transports, Duration::from_secs(60));
+    transports[0].push_error(io::ErrorKind::ConnectionRefused);
+    for _ in 0..3 {
+        transports[1].push_response(200, QUERY_RESPONSE);
+        assert!(solr.query(&SolrQuery::new("*:*")).is_ok());
+    }
+    assert_eq!(transports[0].requests().len(), 1);
+    assert_eq!(transports[1].requests().len(), 3);
+    assert_eq!(solr.alive_nodes(), vec![Url::parse("http://solr1:8983/solr/test/").unwrap()]);
+}
+
+#[test]
+fn node_timing_out_on_connect_is_marked_dead() {
+    let transports = vec![RecordingTransport::new(), RecordingTransport::new()];
+    let solr = lb_client(&transports, Duration::from_secs(60));
+    transports[0].push_connect_timeout();
+    transports[1].push_response(200, QUERY_RESPONSE);
+    assert!(solr.query(&SolrQuery::new("*:*")).is_ok());
+    assert_eq!(transports[0].requests().len(), 1);
+    assert_eq!(solr.alive_nodes(), vec![Url::parse("http://solr1:8983/solr/test/").unwrap()]);
+}
+
+#[test]
+fn read_timeouts_are_not_failed_over() {
+    let transports = vec![RecordingTransport::new(), RecordingTransport::new()];
+    let solr = lb_client(&transports, Duration::from_secs(60));
+    transports[0].push_error(io::ErrorKind::TimedOut);
+    assert!(matches!(solr.query(&SolrQuery::new("*:*")), Err(SolrError::Timeout { connect: false, .. })));
+    assert!(transports[1].requests().is_empty());
+    assert_eq!(solr.alive_nodes().len(), 2);
+}
+
+#[test]
+fn solr_errors_are_not_failed_over() {
+    let transports = vec![RecordingTransport::new(), RecordingTransport::new()];
+    let solr = lb_client(&transports, Duration::from_secs(60));
+    transports[0].push_response(400, r#"{"error":{"msg":"undefined field foo","code":400}}"#);
+    assert!(matches!(solr.query(&SolrQuery::new("foo:bar")), Err(SolrError::Solr { .. })));
+    assert!(transports[1].requests().is_empty());
+    assert_eq!(solr.alive_nodes().len(), 2);
+}
+
+#[test]
+fn dead_nodes_are_tried_when_no_node_is_alive() {
+    let transports = vec![RecordingTransport::new()];
+    let solr = lb_client(&transports, Duration::from_secs(60));
+    transports[0].push_error(io::ErrorKind::ConnectionRefused);
+    assert!(matches!(solr.request(|client| client.commit()), Err(SolrError::Transport { .. })));
+    assert!(solr.alive_nodes().is_empty());
+    assert!(solr.request(|client| client.commit()).is_ok());
+    assert_eq!(solr.alive_nodes().len(), 1);
+}
+
+#[test]
+fn dead_node_returns_after_answering_ping() {
+    let transports = vec![RecordingTransport::new(), RecordingTransport::new()];
+    let solr = lb_client(&transports, Duration::from_millis(20));
+    transports[0].push_error(io::ErrorKind::ConnectionRefused);
+    transports[0].push_response(200, PING_RESPONSE);
+    transports[1].push_response(200, QUERY_RESPONSE);
+    assert!(solr.query(&SolrQuery::new("*:*")).is_ok());
+    for _ in 0..100 {
+        if solr.alive_nodes().len() == 2 {
+            break;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(solr.alive_nodes().len(), 2);
+    assert!(transports[0].requests()[1].url.contains("/admin/ping"));
+}
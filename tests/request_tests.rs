@@ -2,7 +2,7 @@ extern crate rustc_serialize;
 extern crate heliotrope;
 
 use rustc_serialize::json;
-use heliotrope::{SolrDeleteRequest};
+use heliotrope::{SolrDeleteRequest, SolrDocument, SolrUpdateBatch, SolrUpdateOptions};
 
 #[test]
 fn solr_delete_request_to_json() {
@@ -17,3 +17,71 @@ fn solr_delete_request_with_many_ids(){
     let json = json::encode(&request);
     assert_eq!(&json.unwrap().to_string(), r#"{"delete":[{"id":"1"},{"id":"2"}]}"#);
 }
+
+#[test]
+fn solr_delete_request_by_query_to_json(){
+    let request = SolrDeleteRequest::from_query("city:NY");
+    let json = json::encode(&request);
+    assert_eq!(&json.unwrap().to_string(), r#"{"delete":{"query":"city:NY"}}"#);
+}
+
+#[test]
+fn empty_update_batch_to_json() {
+    let batch = SolrUpdateBatch::new();
+    assert!(batch.is_empty());
+    assert_eq!(json::encode(&batch).unwrap(), "{}");
+}
+
+#[test]
+fn update_batch_with_mixed_commands_to_json() {
+    let mut document1 = SolrDocument::new();
+    document1.add_field("id", "1");
+    let mut document2 = SolrDocument::new();
+    document2.add_field("id", "2");
+    let mut batch = SolrUpdateBatch::new();
+    batch.add_many(&[&document1, &document2]);
+    batch.delete_by_id("3");
+    batch.delete_by_query("city:NY");
+    batch.commit();
+    batch.optimize();
+    assert_eq!(batch.len(), 6);
+    assert_eq!(json::encode(&batch).unwrap(),
+               concat!(r#"{"add":{"doc":{"id":"1"}},"add":{"doc":{"id":"2"}},"#,
+                       r#""delete":[{"id":"3"}],"delete":{"query":"city:NY"},"commit":{},"optimize":{}}"#));
+}
+
+#[test]
+fn default_update_options_to_pairs() {
+    let options = SolrUpdateOptions::new();
+    assert_eq!(options.to_pairs(), vec!(("wt".to_string(), "json".to_string())));
+}
+
+#[test]
+fn update_options_with_commit_within_and_overwrite_to_pairs() {
+    let options = SolrUpdateOptions::new().commit_within(5000).overwrite(false).update_chain("dedupe");
+    assert_eq!(options.to_pairs(),
+               vec!(("wt".to_string(), "json".to_string()),
+                    ("commitWithin".to_string(), "5000".to_string()),
+                    ("overwrite".to_string(), "false".to_string()),
+                    ("update.chain".to_string(), "dedupe".to_string())));
+}
+
+#[test]
+fn update_options_with_soft_commit_to_pairs() {
+    let options = SolrUpdateOptions::new().commit(true).soft_commit(true).wait_searcher(false).expunge_deletes(true);
+    assert_eq!(options.to_pairs(),
+               vec!(("wt".to_string(), "json".to_string()),
+                    ("commit".to_string(), "true".to_string()),
+                    ("softCommit".to_string(), "true".to_string()),
+                    ("waitSearcher".to_string(), "false".to_string()),
+                    ("expungeDeletes".to_string(), "true".to_string())));
+}
+
+#[test]
+fn update_options_with_optimize_to_pairs() {
+    let options = SolrUpdateOptions::new().optimize(true).max_segments(4);
+    assert_eq!(options.to_pairs(),
+               vec!(("wt".to_string(), "json".to_string()),
+                    ("optimize".to_string(), "true".to_string()),
+                    ("maxSegments".to_string(), "4".to_string())));
+}
@@ -0,0 +1,82 @@
+extern crate heliotrope;
+extern crate url;
+
+use std::io;
+use std::time::Duration;
+use url::Url;
+use heliotrope::{RecordingTransport, SolrClient, SolrError, SolrQuery, SolrRetryPolicy};
+
+static QUERY_RESPONSE: &str = r#"{"responseHeader":{"status":0,"QTime":1},"response":{"numFound":0,"start":0,"docs":[]}}"#;
+
+fn client(transport: &RecordingTransport, policy: SolrRetryPolicy) -> SolrClient {
+    let url = Url::parse("http://localhost:8983/solr/test/").unwrap();
+    SolrClient::builder(&url).transport(transport.clone()).retry_policy(policy).build()
+}
+
+fn policy() -> SolrRetryPolicy {
+    SolrRetryPolicy::new().initial_backoff(Duration::from_millis(1))
+}
+
+#[test]
+fn query_is_retried_on_unavailable_status() {
+    let transport = RecordingTransport::new();
+    transport.push_response(503, "Service Unavailable");
+    transport.push_error(io::ErrorKind::ConnectionReset);
+    transport.push_response(200, QUERY_RESPONSE);
+    assert!(client(&transport, policy()).query(&SolrQuery::new("*:*")).is_ok());
+    assert_eq!(transport.requests().len(), 3);
+}
+
+#[test]
+fn attempts_are_recorded_on_error() {
+    let transport = RecordingTransport::new();
+    for _ in 0..3 {
+        transport.push_error(io::ErrorKind::ConnectionRefused);
+    }
+    let error = client(&transport, policy()).ping().err().unwrap();
+    assert!(matches!(error, SolrError::Transport { .. }));
+    assert_eq!(error.attempts(), 3);
+}
+
+#[test]
+fn errors_not_configured_are_not_retried() {
+    let transport = RecordingTransport::new();
+    transport.push_error(io::ErrorKind::PermissionDenied);
+    let error = client(&transport, policy()).query(&SolrQuery::new("*:*")).err().unwrap();
+    assert_eq!(error.attempts(), 1);
+    assert_eq!(transport.requests().len(), 1);
+}
+
+#[test]
+fn updates_are_retried_only_when_enabled() {
+    let transport = RecordingTransport::new();
+    transport.push_response(503, "Service Unavailable");
+    assert_eq!(client(&transport, policy()).commit().err().unwrap().attempts(), 1);
+
+    transport.push_response(503, "Service Unavailable");
+    assert!(client(&transport, policy().retry_updates(true)).commit().is_ok());
+    assert_eq!(transport.requests().len(), 3);
+}
+
+#[test]
+fn backoff_doubles_up_to_maximum() {
+    let policy = SolrRetryPolicy::new().jitter(false)
+        .initial_backoff(Duration::from_millis(100))
+        .max_backoff(Duration::from_millis(300));
+    assert_eq!(policy.backoff(1), Duration::from_millis(100));
+    assert_eq!(policy.backoff(2), Duration::from_millis(200));
+    assert_eq!(policy.backoff(3), Duration::from_millis(300));
+    assert_eq!(policy.backoff(40), Duration::from_millis(300));
+    let jittered = policy.jitter(true).backoff(2);
+    assert!(jittered >= Duration::from_millis(100) && jittered <= Duration::from_millis(200));
+}
+
+#[test]
+fn real_time_get_is_retried() {
+    let transport = RecordingTransport::new();
+    transport.push_response(503, "Service Unavailable");
+    transport.push_response(200, r#"{"responseHeader":{"status":0,"QTime":0},"response":{"numFound":1,"start":0,"docs":[{"id":"1"}]}}"#);
+    let document = client(&transport, policy()).get_by_id("1").ok().unwrap().unwrap();
+    assert_eq!(document.get_str("id"), Some("1"));
+    assert_eq!(transport.requests().len(), 2);
+}
@@ -0,0 +1,75 @@
+extern crate heliotrope;
+
+use heliotrope::{SolrClusterState, SolrCompositeIdRouter, SolrDocument, SolrReplica, SolrShard, murmur3_32};
+
+fn shard(name: &str, min: u32, max: u32) -> SolrShard {
+    let replica = SolrReplica {
+        name: "core_node1".to_string(),
+        core: format!("test_{}_replica_n1", name),
+        base_url: "http://127.0.0.1:8983/solr".to_string(),
+        node_name: "127.0.0.1:8983_solr".to_string(),
+        state: "active".to_string(),
+        leader: true
+    };
+    SolrShard { name: name.to_string(), range: Some((min as i32, max as i32)), state: "active".to_string(), replicas: vec![replica] }
+}
+
+fn state() -> SolrClusterState {
+    SolrClusterState {
+        collection: "test".to_string(),
+        router: "compositeId".to_string(),
+        shards: vec![shard("shard1", 0x8000_0000, 0xffff_ffff), shard("shard2", 0, 0x7fff_ffff)],
+        live_nodes: vec!["127.0.0.1:8983_solr".to_string()]
+    }
+}
+
+#[test]
+fn murmur3_matches_solr_hashes() {
+    assert_eq!(murmur3_32(b"", 0), 0);
+    assert_eq!(murmur3_32(b"hello", 0), 0x248b_fa47);
+    assert_eq!(murmur3_32(b"The quick brown fox jumps over the lazy dog", 0), 0x2e4f_f723);
+    assert_eq!(murmur3_32(b"1", 0), 0x9416_ac93);
+    assert_eq!(murmur3_32(b"2", 0), 0x0129_e217);
+}
+
+#[test]
+fn plain_ids_are_hashed_whole() {
+    let router = SolrCompositeIdRouter::new();
+    assert_eq!(router.hash("hello"), 0x248b_fa47);
+    assert_eq!(router.hash("1"), 0x9416_ac93_u32 as i32);
+}
+
+#[test]
+fn route_keys_take_top_bits_of_the_hash() {
+    let router = SolrCompositeIdRouter::new();
+    let hash = |value: &str| murmur3_32(value.as_bytes(), 0);
+    assert_eq!(router.hash("tenant!1") as u32, (hash("tenant") & 0xffff_0000) | (hash("1") & 0x0000_ffff));
+    assert_eq!(router.hash("tenant!user!1") as u32,
+               (hash("tenant") & 0xff00_0000) | (hash("user") & 0x00ff_0000) | (hash("1") & 0x0000_ffff));
+    assert_eq!(router.hash("tenant!") as u32, (hash("tenant") & 0xffff_0000) | (hash("") & 0x0000_ffff));
+    assert_eq!(router.hash("tenant!user!1!2") as u32,
+               (hash("tenant") & 0xff00_0000) | (hash("user") & 0x00ff_0000) | (hash("1!2") & 0x0000_ffff));
+}
+
+#[test]
+fn route_keys_may_set_number_of_bits() {
+    let router = SolrCompositeIdRouter::new();
+    let hash = |value: &str| murmur3_32(value.as_bytes(), 0);
+    assert_eq!(router.hash("tenant/4!1") as u32, (hash("tenant") & 0xf000_0000) | (hash("1") & 0x0fff_ffff));
+    assert_eq!(router.hash("tenant/0!1") as u32, hash("1"));
+    assert_eq!(router.hash("tenant/20!1"), router.hash("tenant!1"));
+    assert_eq!(router.hash("tenant/4!user/4!1") as u32,
+               (hash("tenant") & 0xf000_0000) | (hash("user") & 0x0f00_0000) | (hash("1") & 0x00ff_ffff));
+}
+
+#[test]
+fn documents_are_mapped_to_shards_by_id() {
+    let router = SolrCompositeIdRouter::new().id_field("key");
+    let state = state();
+    let mut document = SolrDocument::new();
+    document.add_field("key", "1");
+    assert_eq!(router.shard_for_document(&state, &document).unwrap().name, "shard1");
+    assert_eq!(router.shard_for_id(&state, "2").unwrap().name, "shard2");
+    assert_eq!(router.shard_for_id(&state, "2!1").unwrap().name, "shard2");
+    assert!(router.shard_for_document(&state, &SolrDocument::new()).is_none());
+}
@@ -2,6 +2,154 @@ extern crate heliotrope;
 extern crate url;
 extern crate hyper;
 
-use url::Url;
-use heliotrope::SolrClient;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+use heliotrope::{SolrClient, SolrError, SolrCsvOptions, SolrDocument, SolrQuery, SolrUpdateOptions, UpdateFormat};
 
+mod common;
+use common::{RecordedRequest, StubSolr, UPDATE_RESPONSE};
+
+fn document(id: usize) -> SolrDocument {
+    let mut document = SolrDocument::new();
+    document.add_field("id", &id.to_string());
+    document
+}
+
+#[test]
+fn add_stream_sends_chunked_json_array() {
+    let solr = StubSolr::new();
+    let client = SolrClient::new(&solr.url);
+    let result = client.add_stream_with_options((1..4).map(document), &SolrUpdateOptions::new().commit(true));
+    assert!(result.is_ok());
+    let requests = solr.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].method, "POST");
+    assert_eq!(requests[0].path, "/solr/test/update?wt=json&commit=true");
+    assert_eq!(requests[0].header("Transfer-Encoding"), Some("chunked"));
+    assert_eq!(requests[0].body, r#"[{"id":"1"},{"id":"2"},{"id":"3"}]"#);
+}
+
+#[test]
+fn add_stream_of_no_documents_sends_empty_array() {
+    let solr = StubSolr::new();
+    let client = SolrClient::new(&solr.url);
+    assert!(client.add_stream(Vec::new().into_iter()).is_ok());
+    assert_eq!(solr.requests()[0].body, "[]");
+}
+
+#[test]
+fn add_csv_posts_csv_with_options() {
+    let solr = StubSolr::new();
+    let client = SolrClient::new(&solr.url);
+    let csv = "id;title\n1;Moby Dick\n";
+    let result = client.add_csv_with_options(csv.as_bytes(), &SolrCsvOptions::new().separator(';'),
+                                             &SolrUpdateOptions::new().commit(true));
+    assert!(result.is_ok());
+    let requests = solr.requests();
+    assert_eq!(requests[0].path, "/solr/test/update?wt=json&commit=true&separator=%3B");
+    assert_eq!(requests[0].header("Content-Type"), Some("text/csv"));
+    assert_eq!(requests[0].body, csv);
+}
+
+#[test]
+fn query_csv_writes_response_body() {
+    let solr = StubSolr::with_handler(|_| (200, "id,title\n1,Moby Dick\n".to_string()));
+    let client = SolrClient::new(&solr.url);
+    let mut out = Vec::new();
+    let written = client.query_csv(&SolrQuery::new("*:*"), &mut out);
+    assert_eq!(written.ok(), Some(21));
+    assert_eq!(String::from_utf8(out).unwrap(), "id,title\n1,Moby Dick\n");
+    assert_eq!(solr.requests()[0].path, "/solr/test/select?wt=csv&q=*%3A*");
+}
+
+#[test]
+fn query_csv_fails_on_error_status() {
+    let solr = StubSolr::with_handler(|_| (400, "undefined field".to_string()));
+    let client = SolrClient::new(&solr.url);
+    let mut out = Vec::new();
+    match client.query_csv(&SolrQuery::new("foo:bar"), &mut out) {
+        Ok(_) => panic!("expected an error"),
+        Err(err) => assert_eq!(err.status(), Some(400))
+    }
+}
+
+#[test]
+fn xml_update_format_posts_xml() {
+    let solr = StubSolr::new();
+    let mut client = SolrClient::new(&solr.url);
+    client.set_update_format(UpdateFormat::Xml);
+    assert!(client.add_and_commit(&document(1)).is_ok());
+    assert!(client.delete_by_id("1").is_ok());
+    assert!(client.add_stream((2..4).map(document)).is_ok());
+    let requests = solr.requests();
+    assert_eq!(requests[0].header("Content-Type"), Some("application/xml"));
+    assert_eq!(requests[0].body, r#"<add><doc><field name="id">1</field></doc></add>"#);
+    assert_eq!(requests[1].body, "<delete><id>1</id></delete>");
+    assert_eq!(requests[2].body, r#"<add><doc><field name="id">2</field></doc><doc><field name="id">3</field></doc></add>"#);
+}
+
+#[test]
+fn client_reuses_pooled_connections() {
+    let solr = StubSolr::new();
+    let client = SolrClient::builder(&solr.url).max_idle_connections(2).build();
+    assert!(client.commit().is_ok());
+    assert!(client.clone().commit().is_ok());
+    let shared = client.clone();
+    thread::spawn(move || assert!(shared.optimize().is_ok())).join().unwrap();
+    let requests = solr.requests();
+    assert_eq!(requests.len(), 3);
+    assert!(requests.iter().all(|r| r.connection == requests[0].connection));
+}
+
+#[test]
+fn read_timeout_fails_with_timeout_error_unless_overridden() {
+    let calls = AtomicUsize::new(0);
+    let solr = StubSolr::with_handler(move |_| {
+        if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+            thread::sleep(Duration::from_millis(500));
+        }
+        (200, UPDATE_RESPONSE.to_string())
+    });
+    let client = SolrClient::builder(&solr.url).read_timeout(Duration::from_millis(100)).build();
+    let error = client.commit().err().unwrap();
+    assert!(matches!(error, SolrError::Timeout { connect: false, .. }));
+    let options = SolrUpdateOptions::new().timeout(Duration::from_secs(5));
+    assert!(client.commit_with_options(&options).is_ok());
+}
+
+#[test]
+fn connection_failure_is_network_error() {
+    let client = SolrClient::builder(&common::unreachable_url()).connect_timeout(Duration::from_secs(1)).build();
+    assert!(matches!(client.commit().err().unwrap(), SolrError::Transport { .. }));
+}
+
+#[test]
+fn basic_auth_header_is_sent_over_http() {
+    let solr = StubSolr::new();
+    let client = SolrClient::builder(&solr.url).basic_auth("solr", "SolrRocks").build();
+    assert!(client.commit().is_ok());
+    assert_eq!(solr.requests()[0].header("Authorization"), Some("Basic c29scjpTb2xyUm9ja3M="));
+}
+
+#[test]
+fn long_queries_are_posted_as_forms() {
+    let solr = StubSolr::with_handler(|_: &RecordedRequest| {
+        (200, r#"{"responseHeader":{"status":0,"QTime":1},"response":{"numFound":1,"start":0,"docs":[{"id":"1"}]}}"#.to_string())
+    });
+    let client = SolrClient::builder(&solr.url).request_compression(10).build();
+    let ids: Vec<String> = (0..1000).map(|id| id.to_string()).collect();
+    let query = SolrQuery::new("*:*").add_filter(&format!("id:({})", ids.join(" OR ")));
+    let response = client.query(&query).ok().unwrap();
+    assert_eq!(response.total, 1);
+    assert!(client.query(&SolrQuery::new("*:*")).is_ok());
+
+    let requests = solr.requests();
+    assert_eq!(requests[0].method, "POST");
+    assert_eq!(requests[0].path, "/solr/test/select");
+    assert_eq!(requests[0].header("Content-Type"), Some("application/x-www-form-urlencoded"));
+    assert!(requests[0].header("Content-Encoding").is_none());
+    assert!(requests[0].body.starts_with("wt=json&q=*%3A*&fq=id%3A%280+OR+1+OR+2"));
+    assert_eq!(requests[1].method, "GET");
+    assert_eq!(requests[1].path, "/solr/test/select?wt=json&q=*%3A*");
+}
@@ -0,0 +1,41 @@
+extern crate heliotrope;
+
+use heliotrope::SolrCsvOptions;
+
+#[test]
+fn default_csv_options_to_pairs() {
+    assert_eq!(SolrCsvOptions::new().to_pairs(), vec!());
+}
+
+#[test]
+fn csv_options_with_format_to_pairs() {
+    let options = SolrCsvOptions::new()
+        .separator('\t')
+        .header(false)
+        .fieldnames(&["id", "title", "tags"])
+        .encapsulator('"')
+        .skip(&["internal"])
+        .skip_lines(2);
+    assert_eq!(options.to_pairs(),
+               vec!(("separator".to_string(), "\t".to_string()),
+                    ("header".to_string(), "false".to_string()),
+                    ("fieldnames".to_string(), "id,title,tags".to_string()),
+                    ("skip".to_string(), "internal".to_string()),
+                    ("skipLines".to_string(), "2".to_string()),
+                    ("encapsulator".to_string(), "\"".to_string())));
+}
+
+#[test]
+fn csv_options_with_splits_and_maps_to_pairs() {
+    let options = SolrCsvOptions::new()
+        .split(true)
+        .split_field("tags", '|')
+        .add_map("N/A", "")
+        .add_field_map("in_stock", "Yes", "true");
+    assert_eq!(options.to_pairs(),
+               vec!(("split".to_string(), "true".to_string()),
+                    ("f.tags.split".to_string(), "true".to_string()),
+                    ("f.tags.separator".to_string(), "|".to_string()),
+                    ("map".to_string(), "N/A:".to_string()),
+                    ("f.in_stock.map".to_string(), "Yes:true".to_string())));
+}
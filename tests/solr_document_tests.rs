@@ -2,7 +2,7 @@ extern crate rustc_serialize;
 extern crate heliotrope;
 
 use rustc_serialize::json;
-use heliotrope::{SolrDocument};
+use heliotrope::{AtomicUpdate, SolrDocument, SolrValue};
 
 #[test]
 fn empty_document_to_json(){
@@ -36,3 +36,74 @@ fn adding_a_field_really_adds_it(){
     assert_eq!(document.fields.len(), 1);
 }
 
+#[test]
+fn typed_getters_return_matching_values(){
+    let mut document = SolrDocument::new();
+    document.add_field("name", "Kvothe");
+    document.add_value("age", SolrValue::I64(16));
+    document.add_value("height", SolrValue::F64(1.75));
+    document.add_value("arcanist", SolrValue::Boolean(true));
+    assert_eq!(document.get_str("name"), Some("Kvothe"));
+    assert_eq!(document.get_i64("age"), Some(16));
+    assert_eq!(document.get_f64("height"), Some(1.75));
+    assert_eq!(document.get_bool("arcanist"), Some(true));
+    assert_eq!(document.get_i64("name"), None);
+    assert_eq!(document.get_str("missing"), None);
+}
+
+#[test]
+fn multi_valued_field_keeps_all_values(){
+    let mut document = SolrDocument::new();
+    document.add_field("friend", "Simmon");
+    document.add_field("name", "Kvothe");
+    document.add_field("friend", "Wilem");
+    assert_eq!(document.get_str("friend"), Some("Simmon"));
+    assert_eq!(document.get_all("friend"),
+               vec!(&SolrValue::String("Simmon".to_string()), &SolrValue::String("Wilem".to_string())));
+    let json = json::encode(&document);
+    assert_eq!(json.unwrap().to_string(), r#"{"friend":"Simmon","name":"Kvothe","friend":"Wilem"}"#);
+}
+
+#[test]
+fn set_field_replaces_all_values_in_place(){
+    let mut document = SolrDocument::new();
+    document.add_field("friend", "Simmon");
+    document.add_field("name", "Kvothe");
+    document.add_field("friend", "Wilem");
+    document.set_field("friend", "Auri");
+    let names: Vec<&str> = document.iter().map(|f| &f.name[..]).collect();
+    assert_eq!(names, vec!("friend", "name"));
+    assert_eq!(document.get_all("friend"), vec!(&SolrValue::String("Auri".to_string())));
+}
+
+#[test]
+fn remove_field_returns_removed_values(){
+    let mut document = SolrDocument::new();
+    document.add_field("friend", "Simmon");
+    document.add_field("name", "Kvothe");
+    document.add_field("friend", "Wilem");
+    let removed = document.remove_field("friend");
+    assert_eq!(removed.len(), 2);
+    assert!(!document.contains_field("friend"));
+    assert_eq!(document.get_str("name"), Some("Kvothe"));
+    assert_eq!(document.len(), 1);
+}
+
+#[test]
+fn document_with_atomic_updates_and_boost_to_json(){
+    let mut document = SolrDocument::new();
+    document.add_field("id", "1");
+    document.add_update("views", AtomicUpdate::Inc, SolrValue::I64(1));
+    document.add_field_with_boost("title", SolrValue::String("Moby Dick".to_string()), 2.0);
+    let json = json::encode(&document);
+    assert_eq!(json.unwrap().to_string(), r#"{"id":"1","views":{"inc":1},"title":{"boost":2.0,"value":"Moby Dick"}}"#);
+}
+
+#[test]
+fn null_value_to_json_removes_field(){
+    let mut document = SolrDocument::new();
+    document.add_field("id", "1");
+    document.add_update("draft", AtomicUpdate::Set, SolrValue::Null);
+    let json = json::encode(&document);
+    assert_eq!(json.unwrap().to_string(), r#"{"id":"1","draft":{"set":null}}"#);
+}
@@ -0,0 +1,46 @@
+extern crate heliotrope;
+extern crate rustc_serialize;
+
+use std::error::Error;
+use std::io;
+use rustc_serialize::json;
+use heliotrope::SolrError;
+
+#[test]
+fn solr_error_payload_is_decoded() {
+    let body = r#"{"responseHeader":{"status":400,"QTime":1},"error":{
+        "metadata":["error-class","org.apache.solr.common.SolrException","root-error-class","org.apache.solr.common.SolrException"],
+        "msg":"undefined field foo","code":400}}"#;
+    match json::decode::<SolrError>(body).unwrap() {
+        SolrError::Solr { status, code, msg, metadata, trace, attempts } => {
+            assert_eq!((status, code, &msg[..], attempts), (400, 400, "undefined field foo", 1));
+            assert_eq!(metadata[0], ("error-class".to_string(), "org.apache.solr.common.SolrException".to_string()));
+            assert_eq!(metadata.len(), 2);
+            assert_eq!(trace, None);
+        },
+        err => panic!("unexpected error {:?}", err)
+    }
+}
+
+#[test]
+fn solr_error_display_and_source() {
+    let error = SolrError::Transport { source: io::Error::new(io::ErrorKind::ConnectionRefused, "refused"), attempts: 2 };
+    assert_eq!(error.to_string(), "Http error: refused");
+    assert_eq!(error.source().unwrap().to_string(), "refused");
+    assert_eq!(error.attempts(), 2);
+    assert_eq!(error.status(), None);
+
+    let error = SolrError::Http { status: 503, body: "Service Unavailable".to_string(), attempts: 1 };
+    assert_eq!(error.to_string(), "HTTP status 503: Service Unavailable");
+    assert_eq!(error.status(), Some(503));
+    assert!(error.source().is_none());
+}
+
+#[test]
+fn parse_error_carries_source() {
+    let source = json::Json::from_str("{").err().unwrap();
+    let error = SolrError::Parse { message: "Parse error".to_string(), source: Some(Box::new(source)) };
+    assert!(error.to_string().starts_with("Parse error: "));
+    assert!(error.source().is_some());
+    assert_eq!(SolrError::parse("Bad response").to_string(), "Bad response");
+}
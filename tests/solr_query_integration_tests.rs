@@ -12,6 +12,6 @@ fn simple_query_test() {
     let query = SolrQuery::new("*:*");
     match client.query(&query) {
         Ok(query_response) => println!("{:?}", query_response),
-        Err(e) => panic!(e.message)
+        Err(e) => panic!("{}", e)
     }
 }
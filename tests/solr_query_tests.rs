@@ -1,6 +1,9 @@
 extern crate heliotrope;
+extern crate rustc_serialize;
 
-use heliotrope::{SolrQuery, SortClause, SortOrder};
+use std::time::Duration;
+use rustc_serialize::json::Json;
+use heliotrope::{SolrBoolQuery, SolrQuery, SortClause, SortOrder};
 
 #[test]
 fn query_only_query_to_pairs() {
@@ -149,3 +152,51 @@ fn query_and_custom_start_and_rows() {
                     ("start".to_string(), "125".to_string()),
                     ("rows".to_string(), "25".to_string())));
 }
+
+#[test]
+fn query_and_time_allowed() {
+    let query = SolrQuery::new("abba").time_allowed(500).timeout(Duration::from_secs(2));
+    assert_eq!(query.to_pairs(),
+               vec!(("wt".to_string(), "json".to_string()),
+                    ("q".to_string(), "abba".to_string()),
+                    ("timeAllowed".to_string(), "500".to_string())));
+    assert_eq!(query.get_timeout(), Some(Duration::from_secs(2)));
+}
+
+#[test]
+fn query_with_handler_and_qt() {
+    let query = SolrQuery::new("abba").handler("/browse").qt("dismax");
+    assert_eq!(query.to_pairs(),
+               vec!(("wt".to_string(), "json".to_string()),
+                    ("q".to_string(), "abba".to_string()),
+                    ("qt".to_string(), "dismax".to_string())));
+    assert_eq!(query.get_handler(), Some("/browse"));
+    assert_eq!(SolrQuery::new("abba").get_handler(), None);
+}
+
+#[test]
+fn query_to_json_request() {
+    let query = SolrQuery::new("title:moby")
+        .add_field("id").add_field("title")
+        .add_filter("type:book")
+        .add_sort("year", SortOrder::Descending)
+        .start(20)
+        .time_allowed(500);
+    assert_eq!(query.to_json().to_string(),
+               r#"{"fields":["id","title"],"filter":["type:book"],"limit":10,"offset":20,"params":{"timeAllowed":500,"wt":"json"},"query":"title:moby","sort":"year desc"}"#);
+}
+
+#[test]
+fn bool_query_and_facets_to_json_request() {
+    let bool_query = SolrBoolQuery::new()
+        .must("title:rust")
+        .should(&SolrBoolQuery::new().must("author:klabnik").must_not("year:[* TO 2015]"))
+        .filter("type:book");
+    let query = SolrQuery::new("*:*")
+        .json_query(&bool_query)
+        .add_json_facet("genres", Json::from_str(r#"{"type":"terms","field":"genre"}"#).unwrap());
+    let json = query.to_json();
+    assert_eq!(json.find("query").unwrap().to_string(),
+               r#"{"bool":{"filter":["type:book"],"must":["title:rust"],"should":[{"bool":{"must":["author:klabnik"],"must_not":["year:[* TO 2015]"]}}]}}"#);
+    assert_eq!(json.find_path(&["facet", "genres", "field"]).unwrap().as_string(), Some("genre"));
+}
@@ -0,0 +1,65 @@
+extern crate heliotrope;
+
+use heliotrope::{AtomicUpdate, SolrDeleteRequest, SolrDocument, SolrUpdateBatch, SolrValue, ToXml};
+
+#[test]
+fn document_to_xml_escapes_names_and_values() {
+    let mut document = SolrDocument::new();
+    document.add_field("id", "1");
+    document.add_field("title", "Tom & Jerry <\"cartoon\">");
+    document.add_value("year", SolrValue::I64(1940));
+    document.add_value("active", SolrValue::Boolean(false));
+    assert_eq!(document.to_xml(),
+               concat!(r#"<doc><field name="id">1</field>"#,
+                       r#"<field name="title">Tom &amp; Jerry &lt;&quot;cartoon&quot;&gt;</field>"#,
+                       r#"<field name="year">1940</field><field name="active">false</field></doc>"#));
+}
+
+#[test]
+fn document_to_xml_with_boosts_and_atomic_updates() {
+    let mut document = SolrDocument::new();
+    document.set_boost(2.5);
+    document.add_field("id", "1");
+    document.add_field_with_boost("title", SolrValue::String("Moby Dick".to_string()), 3.0);
+    document.add_update("views", AtomicUpdate::Inc, SolrValue::I64(1));
+    document.add_update("draft", AtomicUpdate::Set, SolrValue::Null);
+    assert_eq!(document.to_xml(),
+               concat!(r#"<doc boost="2.5"><field name="id">1</field>"#,
+                       r#"<field name="title" boost="3">Moby Dick</field>"#,
+                       r#"<field name="views" update="inc">1</field>"#,
+                       r#"<field name="draft" update="set" null="true"/></doc>"#));
+}
+
+#[test]
+fn delete_requests_to_xml() {
+    let by_ids = SolrDeleteRequest::from_ids(&vec!["1".to_string(), "2".to_string()]);
+    assert_eq!(by_ids.to_xml(), "<delete><id>1</id><id>2</id></delete>");
+    let by_query = SolrDeleteRequest::from_query("title:\"a & b\"");
+    assert_eq!(by_query.to_xml(), "<delete><query>title:&quot;a &amp; b&quot;</query></delete>");
+}
+
+#[test]
+fn update_batch_to_xml() {
+    let mut document = SolrDocument::new();
+    document.add_field("id", "1");
+    let mut batch = SolrUpdateBatch::new();
+    batch.add(&document);
+    batch.delete_by_id("2");
+    batch.commit();
+    batch.optimize();
+    batch.rollback();
+    assert_eq!(batch.to_xml(),
+               concat!(r#"<update><add><doc><field name="id">1</field></doc></add>"#,
+                       r#"<delete><id>2</id></delete><commit/><optimize/><rollback/></update>"#));
+}
+
+#[test]
+fn documents_to_xml_add_command() {
+    let mut document1 = SolrDocument::new();
+    document1.add_field("id", "1");
+    let mut document2 = SolrDocument::new();
+    document2.add_field("id", "2");
+    let documents = [&document1, &document2];
+    assert_eq!(documents[..].to_xml(),
+               r#"<add><doc><field name="id">1</field></doc><doc><field name="id">2</field></doc></add>"#);
+}
@@ -0,0 +1,181 @@
+extern crate heliotrope;
+extern crate openssl;
+extern crate url;
+
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use openssl::asn1::Asn1Time;
+use openssl::bn::BigNum;
+use openssl::hash::MessageDigest;
+use openssl::pkey::{PKey, Private};
+use openssl::rsa::Rsa;
+use openssl::ssl::{SslAcceptor, SslMethod, SslVerifyMode};
+use openssl::x509::{X509, X509NameBuilder};
+use openssl::x509::extension::SubjectAlternativeName;
+use url::Url;
+use heliotrope::{SolrClient, SolrCredentials, SolrProxyConfig, SolrTlsConfig};
+
+mod common;
+use common::{RecordedRequest, UPDATE_RESPONSE};
+
+/// A self-signed certificate for localhost, with PEM files for the client
+struct Certificate {
+    x509: X509,
+    key: PKey<Private>,
+    cert_file: PathBuf,
+    key_file: PathBuf
+}
+
+fn self_signed_certificate(name: &str) -> Certificate {
+    let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+    let mut subject = X509NameBuilder::new().unwrap();
+    subject.append_entry_by_text("CN", "localhost").unwrap();
+    let subject = subject.build();
+    let mut builder = X509::builder().unwrap();
+    builder.set_version(2).unwrap();
+    builder.set_serial_number(&BigNum::from_u32(1).unwrap().to_asn1_integer().unwrap()).unwrap();
+    builder.set_subject_name(&subject).unwrap();
+    builder.set_issuer_name(&subject).unwrap();
+    builder.set_pubkey(&key).unwrap();
+    builder.set_not_before(&Asn1Time::days_from_now(0).unwrap()).unwrap();
+    builder.set_not_after(&Asn1Time::days_from_now(1).unwrap()).unwrap();
+    let alt_names = SubjectAlternativeName::new().dns("localhost").ip("127.0.0.1")
+        .build(&builder.x509v3_context(None, None)).unwrap();
+    builder.append_extension(alt_names).unwrap();
+    builder.sign(&key, MessageDigest::sha256()).unwrap();
+    let x509 = builder.build();
+
+    let dir = std::env::temp_dir();
+    let cert_file = dir.join(format!("heliotrope-{}-{}.crt", std::process::id(), name));
+    let key_file = dir.join(format!("heliotrope-{}-{}.key", std::process::id(), name));
+    File::create(&cert_file).unwrap().write_all(&x509.to_pem().unwrap()).unwrap();
+    File::create(&key_file).unwrap().write_all(&key.private_key_to_pem_pkcs8().unwrap()).unwrap();
+    Certificate { x509, key, cert_file, key_file }
+}
+
+impl Drop for Certificate {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.cert_file);
+        let _ = fs::remove_file(&self.key_file);
+    }
+}
+
+/// Starts an HTTPS stand-in for Solr, requiring a client certificate if `client_ca` is given
+fn https_solr(server: &Certificate, client_ca: Option<&Certificate>) -> (Url, Arc<Mutex<Vec<RecordedRequest>>>) {
+    let mut acceptor = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls()).unwrap();
+    acceptor.set_private_key(&server.key).unwrap();
+    acceptor.set_certificate(&server.x509).unwrap();
+    if let Some(ca) = client_ca {
+        acceptor.cert_store_mut().add_cert(ca.x509.clone()).unwrap();
+        acceptor.set_verify(SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT);
+    }
+    let acceptor = Arc::new(acceptor.build());
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let recorded = requests.clone();
+    let handler: common::Handler = Arc::new(|_: &RecordedRequest| (200, UPDATE_RESPONSE.to_string()));
+    thread::spawn(move || {
+        for (connection, stream) in listener.incoming().enumerate() {
+            let (acceptor, recorded, handler) = (acceptor.clone(), recorded.clone(), handler.clone());
+            thread::spawn(move || {
+                if let Ok(stream) = acceptor.accept(stream.unwrap()) {
+                    common::serve(connection, stream, recorded, handler);
+                }
+            });
+        }
+    });
+    (Url::parse(&format!("https://localhost:{}/solr/test/", port)).unwrap(), requests)
+}
+
+/// Starts a proxy tunneling connections with CONNECT, recording request heads
+fn connect_proxy() -> (Url, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let heads = Arc::new(Mutex::new(Vec::new()));
+    let recorded = heads.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut client = stream.unwrap();
+            let mut head = Vec::new();
+            let mut byte = [0; 1];
+            while !head.ends_with(b"\r\n\r\n") && client.read(&mut byte).unwrap() == 1 {
+                head.push(byte[0]);
+            }
+            let head = String::from_utf8(head).unwrap();
+            let target = head.split_whitespace().nth(1).unwrap().to_string();
+            recorded.lock().unwrap().push(head);
+            let server = TcpStream::connect(&target[..]).unwrap();
+            client.write_all(b"HTTP/1.1 200 Connection established\r\n\r\n").unwrap();
+            let (mut client_in, mut server_out) = (client.try_clone().unwrap(), server.try_clone().unwrap());
+            thread::spawn(move || {
+                let _ = io::copy(&mut client_in, &mut server_out);
+                let _ = server_out.shutdown(Shutdown::Write);
+            });
+            let (mut server_in, mut client_out) = (server, client);
+            thread::spawn(move || {
+                let _ = io::copy(&mut server_in, &mut client_out);
+                let _ = client_out.shutdown(Shutdown::Write);
+            });
+        }
+    });
+    (Url::parse(&format!("http://127.0.0.1:{}", port)).unwrap(), heads)
+}
+
+#[test]
+fn https_with_custom_ca_file() {
+    let server = self_signed_certificate("ca-server");
+    let (url, requests) = https_solr(&server, None);
+    let client = SolrClient::builder(&url).tls(SolrTlsConfig::new().ca_file(&server.cert_file)).build();
+    assert!(client.commit().is_ok());
+    assert_eq!(requests.lock().unwrap()[0].path, "/solr/test/update?wt=json&commit=true");
+}
+
+#[test]
+fn https_rejects_untrusted_certificate_unless_invalid_accepted() {
+    let server = self_signed_certificate("untrusted-server");
+    let (url, _) = https_solr(&server, None);
+    assert!(SolrClient::new(&url).commit().is_err());
+    let client = SolrClient::builder(&url).tls(SolrTlsConfig::new().accept_invalid_certs(true)).build();
+    assert!(client.commit().is_ok());
+}
+
+#[test]
+fn https_with_client_certificate() {
+    let server = self_signed_certificate("mtls-server");
+    let client_cert = self_signed_certificate("mtls-client");
+    let (url, _) = https_solr(&server, Some(&client_cert));
+    let tls = SolrTlsConfig::new().ca_file(&server.cert_file);
+    assert!(SolrClient::builder(&url).tls(tls.clone()).build().commit().is_err());
+    let tls = tls.client_certificate(&client_cert.cert_file, &client_cert.key_file);
+    assert!(SolrClient::builder(&url).tls(tls).build().commit().is_ok());
+}
+
+#[test]
+fn invalid_tls_configuration_fails_https_requests() {
+    let url = Url::parse("https://localhost:8984/solr/test/").unwrap();
+    let client = SolrClient::builder(&url).tls(SolrTlsConfig::new().ca_file("/nonexistent/ca.pem")).build();
+    let error = client.commit().err().unwrap();
+    assert!(error.to_string().contains("Invalid TLS configuration"));
+}
+
+#[test]
+fn https_is_tunneled_through_proxy() {
+    let server = self_signed_certificate("proxy-server");
+    let (url, requests) = https_solr(&server, None);
+    let (proxy_url, heads) = connect_proxy();
+    let client = SolrClient::builder(&url)
+        .tls(SolrTlsConfig::new().ca_file(&server.cert_file))
+        .proxy(SolrProxyConfig::new(&proxy_url).credentials("agent", "secret"))
+        .build();
+    assert!(client.commit().is_ok());
+    assert_eq!(requests.lock().unwrap()[0].path, "/solr/test/update?wt=json&commit=true");
+    let head = heads.lock().unwrap()[0].clone();
+    assert!(head.starts_with(&format!("CONNECT localhost:{} HTTP/1.1", url.port().unwrap())));
+    let authorization = SolrCredentials::new("agent", "secret").authorization();
+    assert!(head.contains(&format!("Proxy-Authorization: {}", authorization)));
+}
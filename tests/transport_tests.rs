@@ -0,0 +1,241 @@
+extern crate heliotrope;
+extern crate url;
+
+use std::io;
+use std::time::Duration;
+use url::Url;
+use heliotrope::{HttpMethod, RecordingTransport, SolrClient, SolrDocument, SolrError, SolrQuery, SolrUpdateOptions};
+
+fn client(transport: &RecordingTransport) -> SolrClient {
+    let url = Url::parse("http://localhost:8983/solr/test/").unwrap();
+    SolrClient::builder(&url).transport(transport.clone()).build()
+}
+
+#[test]
+fn recording_transport_records_update_request() {
+    let transport = RecordingTransport::new();
+    let mut document = SolrDocument::new();
+    document.add_field("id", "1");
+    let result = client(&transport).add_with_options(&document, &SolrUpdateOptions::new().commit(true));
+    assert!(result.is_ok());
+    let requests = transport.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].method, HttpMethod::Post);
+    assert_eq!(requests[0].url, "http://localhost:8983/solr/test/update?wt=json&commit=true");
+    assert_eq!(requests[0].header("content-type"), Some("application/json"));
+    assert_eq!(requests[0].body, r#"[{"id":"1"}]"#);
+}
+
+#[test]
+fn recording_transport_answers_with_queued_responses() {
+    let transport = RecordingTransport::new();
+    transport.push_response(200, r#"{"responseHeader":{"status":0,"QTime":3},
+        "response":{"numFound":1,"start":0,"docs":[{"id":"1","title":"Moby Dick"}]}}"#);
+    let response = client(&transport).query(&SolrQuery::new("title:moby")).ok().unwrap();
+    assert_eq!(response.time, 3);
+    assert_eq!(response.items[0].get_str("title"), Some("Moby Dick"));
+    let requests = transport.requests();
+    assert_eq!(requests[0].method, HttpMethod::Get);
+    assert!(requests[0].url.starts_with("http://localhost:8983/solr/test/select?"));
+    assert_eq!(requests[0].body, "");
+}
+
+#[test]
+fn query_reports_partial_results_and_timeout_override() {
+    let transport = RecordingTransport::new();
+    transport.push_response(200, r#"{"responseHeader":{"status":0,"QTime":500,"partialResults":true},
+        "response":{"numFound":0,"start":0,"docs":[]}}"#);
+    let query = SolrQuery::new("*:*").time_allowed(500).timeout(Duration::from_secs(1));
+    let response = client(&transport).query(&query).ok().unwrap();
+    assert!(response.partial_results);
+    let requests = transport.requests();
+    assert!(requests[0].url.ends_with("&timeAllowed=500"));
+    assert_eq!(requests[0].timeout, Some(Duration::from_secs(1)));
+}
+
+#[test]
+fn recording_transport_reports_queued_errors() {
+    let transport = RecordingTransport::new();
+    transport.push_error(io::ErrorKind::ConnectionRefused);
+    assert!(matches!(client(&transport).commit().err().unwrap(), SolrError::Transport { .. }));
+    assert!(client(&transport).commit().is_ok());
+    assert_eq!(transport.requests().len(), 2);
+}
+
+#[test]
+fn basic_auth_and_headers_are_sent_with_requests() {
+    let transport = RecordingTransport::new();
+    let url = Url::parse("http://localhost:8983/solr/test/").unwrap();
+    let client = SolrClient::builder(&url)
+        .transport(transport.clone())
+        .basic_auth("solr", "SolrRocks")
+        .default_header("X-Api-Key", "secret")
+        .default_header("X-Tenant", "default")
+        .build();
+    assert!(client.commit_with_options(&SolrUpdateOptions::new().add_header("x-tenant", "books")).is_ok());
+    let request = &transport.requests()[0];
+    assert_eq!(request.header("Authorization"), Some("Basic c29scjpTb2xyUm9ja3M="));
+    assert_eq!(request.header("X-Api-Key"), Some("secret"));
+    assert_eq!(request.header("X-Tenant"), Some("books"));
+    assert_eq!(request.headers.iter().filter(|h| h.0.eq_ignore_ascii_case("x-tenant")).count(), 1);
+}
+
+#[test]
+fn debug_output_hides_credentials() {
+    let url = Url::parse("http://localhost:8983/solr/test/").unwrap();
+    let builder = SolrClient::builder(&url).basic_auth("solr", "SolrRocks").default_header("X-Api-Key", "secret");
+    let output = format!("{:?} {:?}", builder, builder.build());
+    assert!(output.contains("solr"));
+    assert!(output.contains("X-Api-Key"));
+    assert!(!output.contains("SolrRocks"));
+    assert!(!output.contains("secret"));
+}
+
+#[test]
+fn solr_error_body_is_returned_with_http_status() {
+    let transport = RecordingTransport::new();
+    transport.push_response(400, r#"{"responseHeader":{"status":400,"QTime":1},"error":{
+        "metadata":["error-class","org.apache.solr.common.SolrException"],"msg":"undefined field foo","code":400}}"#);
+    match client(&transport).query(&SolrQuery::new("foo:bar")).err().unwrap() {
+        SolrError::Solr { status, msg, metadata, .. } => {
+            assert_eq!(status, 400);
+            assert_eq!(msg, "undefined field foo");
+            assert_eq!(metadata[0].1, "org.apache.solr.common.SolrException");
+        },
+        err => panic!("unexpected error {:?}", err)
+    }
+
+    transport.push_response(500, r#"{"error":{"msg":"java.lang.NullPointerException","trace":"at org.apache.solr","code":500}}"#);
+    match client(&transport).commit().err().unwrap() {
+        SolrError::Solr { status, trace, .. } => assert_eq!((status, trace), (500, Some("at org.apache.solr".to_string()))),
+        err => panic!("unexpected error {:?}", err)
+    }
+}
+
+#[test]
+fn error_status_without_solr_error_body() {
+    let transport = RecordingTransport::new();
+    transport.push_response(502, "<html>Bad Gateway</html>");
+    match client(&transport).ping().err().unwrap() {
+        SolrError::Http { status, body, .. } => assert_eq!((status, &body[..]), (502, "<html>Bad Gateway</html>")),
+        err => panic!("unexpected error {:?}", err)
+    }
+}
+
+#[test]
+fn collection_clients_share_transport_and_configuration() {
+    let transport = RecordingTransport::new();
+    let url = Url::parse("http://localhost:8983/solr/").unwrap();
+    let solr = SolrClient::builder(&url)
+        .transport(transport.clone())
+        .basic_auth("solr", "SolrRocks")
+        .default_header("X-Tenant", "default")
+        .build();
+    let books = solr.collection("books");
+    assert_eq!(books.base_url.serialize(), "http://localhost:8983/solr/books/");
+    assert!(books.commit().is_ok());
+    assert!(solr.collection("authors").delete_by_id("1").is_ok());
+    let requests = transport.requests();
+    assert_eq!(requests[0].url, "http://localhost:8983/solr/books/update?wt=json&commit=true");
+    assert_eq!(requests[1].url, "http://localhost:8983/solr/authors/update?wt=json&commit=true");
+    for request in &requests {
+        assert_eq!(request.header("Authorization"), Some("Basic c29scjpTb2xyUm9ja3M="));
+        assert_eq!(request.header("X-Tenant"), Some("default"));
+    }
+}
+
+#[test]
+fn requests_are_sent_to_custom_handlers() {
+    let transport = RecordingTransport::new();
+    transport.push_response(200, r#"{"responseHeader":{"status":0,"QTime":1},"response":{"numFound":0,"start":0,"docs":[]}}"#);
+    let solr = client(&transport);
+    assert!(solr.query(&SolrQuery::new("*:*").handler("/search")).is_ok());
+    let mut document = SolrDocument::new();
+    document.add_field("id", "1");
+    assert!(solr.add_with_options(&document, &SolrUpdateOptions::new().handler("update/json/docs")).is_ok());
+    assert!(solr.commit().is_ok());
+    let requests = transport.requests();
+    assert_eq!(requests[0].url, "http://localhost:8983/solr/test/search?wt=json&q=*%3A*");
+    assert_eq!(requests[1].url, "http://localhost:8983/solr/test/update/json/docs?wt=json");
+    assert_eq!(requests[2].url, "http://localhost:8983/solr/test/update?wt=json&commit=true");
+}
+
+#[test]
+fn query_method_can_be_set() {
+    let transport = RecordingTransport::new();
+    for _ in 0..3 {
+        transport.push_response(200, r#"{"responseHeader":{"status":0,"QTime":1},"response":{"numFound":0,"start":0,"docs":[]}}"#);
+    }
+    let url = Url::parse("http://localhost:8983/solr/test/").unwrap();
+    let solr = SolrClient::builder(&url).transport(transport.clone()).max_get_query_length(10).build();
+    assert!(solr.query(&SolrQuery::new("title:moby").method(HttpMethod::Get)).is_ok());
+    assert!(solr.query(&SolrQuery::new("*:*").handler("/search").method(HttpMethod::Post)).is_ok());
+    assert!(client(&transport).query(&SolrQuery::new("title:moby")).is_ok());
+
+    let requests = transport.requests();
+    assert_eq!((requests[0].method, &requests[0].url[..]), (HttpMethod::Get, "http://localhost:8983/solr/test/select?wt=json&q=title%3Amoby"));
+    assert_eq!((requests[1].method, &requests[1].url[..]), (HttpMethod::Post, "http://localhost:8983/solr/test/search"));
+    assert_eq!(requests[1].body, "wt=json&q=*%3A*");
+    assert_eq!(requests[2].method, HttpMethod::Get);
+}
+
+#[test]
+fn json_query_is_posted_to_query_handler() {
+    let transport = RecordingTransport::new();
+    transport.push_response(200, r#"{"responseHeader":{"status":0,"QTime":2},
+        "response":{"numFound":1,"start":0,"docs":[{"id":"1","title":"Moby Dick"}]}}"#);
+    let response = client(&transport).query_json(&SolrQuery::new("title:moby").rows(5)).ok().unwrap();
+    assert_eq!(response.items[0].get_str("title"), Some("Moby Dick"));
+    transport.push_response(200, r#"{"responseHeader":{"status":0,"QTime":1},"response":{"numFound":0,"start":0,"docs":[]}}"#);
+    assert!(client(&transport).query_json(&SolrQuery::new("*:*").handler("/search")).is_ok());
+
+    let requests = transport.requests();
+    assert_eq!(requests[0].method, HttpMethod::Post);
+    assert_eq!(requests[0].url, "http://localhost:8983/solr/test/query");
+    assert_eq!(requests[0].header("content-type"), Some("application/json"));
+    assert_eq!(requests[0].body, r#"{"limit":5,"params":{"wt":"json"},"query":"title:moby"}"#);
+    assert_eq!(requests[1].url, "http://localhost:8983/solr/test/search");
+}
+
+#[test]
+fn rollback_accepts_update_options() {
+    let transport = RecordingTransport::new();
+    let options = SolrUpdateOptions::new().add_header("X-Tenant", "books").timeout(Duration::from_secs(2));
+    assert!(client(&transport).rollback_with_options(&options).is_ok());
+    assert!(client(&transport).rollback().is_ok());
+    let requests = transport.requests();
+    assert_eq!(requests[0].url, "http://localhost:8983/solr/test/update?wt=json&rollback=true");
+    assert_eq!(requests[0].header("X-Tenant"), Some("books"));
+    assert_eq!(requests[0].timeout, Some(Duration::from_secs(2)));
+    assert!(requests[1].header("X-Tenant").is_none());
+}
+
+#[test]
+fn document_boosts_are_sent_as_json_add_commands() {
+    let transport = RecordingTransport::new();
+    let mut boosted = SolrDocument::new();
+    boosted.add_field("id", "1");
+    boosted.set_boost(2.5);
+    let mut plain = SolrDocument::new();
+    plain.add_field("id", "2");
+    assert!(client(&transport).add_many(&[&boosted, &plain]).is_ok());
+    assert!(client(&transport).add(&plain).is_ok());
+    let requests = transport.requests();
+    assert_eq!(requests[0].body, r#"{"add":{"boost":2.5,"doc":{"id":"1"}},"add":{"doc":{"id":"2"}}}"#);
+    assert_eq!(requests[1].body, r#"[{"id":"2"}]"#);
+}
+
+#[test]
+fn documents_are_fetched_by_real_time_get() {
+    let transport = RecordingTransport::new();
+    transport.push_response(200, r#"{"responseHeader":{"status":0,"QTime":0},
+        "response":{"numFound":2,"start":0,"docs":[{"id":"1"},{"id":"a,b"}]}}"#);
+    transport.push_response(200, r#"{"responseHeader":{"status":0,"QTime":0},"response":{"numFound":0,"start":0,"docs":[]}}"#);
+    let response = client(&transport).get_by_ids(&["1", "a,b"]).ok().unwrap();
+    assert_eq!(response.items[1].get_str("id"), Some("a,b"));
+    assert!(client(&transport).get_by_id("2").ok().unwrap().is_none());
+    let requests = transport.requests();
+    assert_eq!(requests[0].method, HttpMethod::Get);
+    assert_eq!(requests[0].url, "http://localhost:8983/solr/test/get?wt=json&omitHeader=false&ids=1%2Ca%5C%2Cb");
+    assert_eq!(requests[1].url, "http://localhost:8983/solr/test/get?wt=json&omitHeader=false&ids=2");
+}